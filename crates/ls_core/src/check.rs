@@ -0,0 +1,64 @@
+//! Checking a whole project from the command line, without a client
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{Diagnostic, PositionEncodingKind};
+
+use crate::{
+    Workspace,
+    config::Config,
+    diagnostics::template_diagnostics,
+    document::Document,
+    uri::DocumentUri,
+    workspace::{find_scripts, find_templates},
+};
+
+/// Diagnostics of one template, or of the templates embedded in one script
+#[derive(Debug, Clone)]
+pub struct FileDiagnostics {
+    /// Path of the file, under the root that was checked
+    pub path: PathBuf,
+    /// The diagnostics, with `character` counted in unicode code points
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Reports the diagnostics the server would publish for each template in the
+/// project at `root` and for the templates embedded in its scripts, or for
+/// `root` itself when it's a file. Hidden directories and `node_modules` are
+/// skipped, and so are the HTML files `config` doesn't take for templates and
+/// the scripts it excludes.
+///
+/// Files without problems aren't listed
+pub fn check_project(root: &Path, config: Config) -> io::Result<Vec<FileDiagnostics>> {
+    let mut files = vec![];
+    let mut workspace = if root.is_dir() {
+        find_templates(root, &mut files)?;
+        find_scripts(root, &mut files)?;
+        Workspace::with_root(std::path::absolute(root)?)
+    } else {
+        files.push(root.to_owned());
+        Workspace::new()
+    }
+    .with_position_encoding(PositionEncodingKind::UTF32);
+    workspace.set_config(config).map_err(io::Error::other)?;
+    files.sort();
+
+    let mut results = vec![];
+    for path in files {
+        let uri = DocumentUri::from_file_path(&std::path::absolute(&path)?);
+        // a file given on its own is checked whatever the globs say
+        if root.is_dir() && !workspace.has_diagnostics(&uri) {
+            continue;
+        }
+        let text = fs::read_to_string(&path)?;
+        let document = Document::new(uri, text, None);
+        let diagnostics = template_diagnostics(&document, &workspace);
+        if !diagnostics.is_empty() {
+            results.push(FileDiagnostics { path, diagnostics });
+        }
+    }
+    Ok(results)
+}
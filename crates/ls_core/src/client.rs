@@ -0,0 +1,151 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicI64, Ordering},
+    },
+};
+
+use lsp_types::{
+    LogMessageParams, LogTraceParams, MessageType, TraceValue,
+    notification::{self, Notification},
+    request::Request,
+};
+use serde::Serialize;
+use tracing::{debug, error};
+
+use crate::{
+    jsonrpc::{JsonRpcRequestId, LSMessageNotification, LSMessageRequest},
+    record::{Direction, Recorder},
+};
+
+/// Handle for sending messages to the client.
+///
+/// It's cheap to clone and can be used from outside the server loop,
+/// e.g. by a `tracing` layer that forwards log events to the editor
+#[derive(Clone)]
+pub struct Client {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// Set by the client through `initialize` and `$/setTrace`
+    trace: Arc<Mutex<TraceValue>>,
+    /// Where messages are recorded to, if anywhere
+    recorder: Arc<Mutex<Option<Recorder>>>,
+    /// Id of the next request sent to the client
+    next_request_id: Arc<AtomicI64>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(Box::new(io::stdout()))
+    }
+}
+
+impl Client {
+    pub(crate) fn new(output: Box<dyn Write + Send>) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            trace: Arc::new(Mutex::new(TraceValue::Off)),
+            recorder: Arc::new(Mutex::new(None)),
+            next_request_id: Arc::new(AtomicI64::new(1)),
+        }
+    }
+
+    /// Sends a `window/logMessage` notification
+    pub fn log_message(&self, typ: MessageType, message: String) {
+        self.notify::<notification::LogMessage>(LogMessageParams { typ, message });
+    }
+
+    /// Sends a `$/logTrace` notification if the client asked for traces.
+    /// `verbose` is only sent when the trace level is `verbose`
+    pub fn log_trace(&self, message: String, verbose: impl FnOnce() -> String) {
+        let verbose = match self.trace() {
+            TraceValue::Off => return,
+            TraceValue::Messages => None,
+            TraceValue::Verbose => Some(verbose()),
+        };
+        self.notify::<notification::LogTrace>(LogTraceParams { message, verbose });
+    }
+
+    pub fn trace(&self) -> TraceValue {
+        *self.trace.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn set_trace(&self, trace: TraceValue) {
+        *self.trace.lock().unwrap_or_else(PoisonError::into_inner) = trace;
+    }
+
+    pub(crate) fn set_recorder(&self, recorder: Recorder) {
+        *self.recorder.lock().unwrap_or_else(PoisonError::into_inner) = Some(recorder);
+    }
+
+    /// Records `message` if a recorder is set
+    pub(crate) fn record(&self, direction: Direction, message: impl FnOnce() -> serde_json::Value) {
+        let result = {
+            let mut recorder = self.recorder.lock().unwrap_or_else(PoisonError::into_inner);
+            match recorder.as_mut() {
+                Some(recorder) => recorder.record(direction, message()),
+                None => return,
+            }
+        };
+        if let Err(err) = result {
+            error!("Couldn't record message: {err}");
+        }
+    }
+
+    pub(crate) fn notify<N: Notification>(&self, params: N::Params)
+    where
+        N::Params: Serialize,
+    {
+        match serde_json::to_value(params) {
+            Ok(params) => self.send(&LSMessageNotification::new(N::METHOD, params)),
+            Err(err) => error!("Couldn't serialize params of '{}': {err}", N::METHOD),
+        }
+    }
+
+    /// Sends the request `R` to the client. Its response comes back
+    /// through the server loop with the returned id
+    pub(crate) fn request<R: Request>(&self, params: R::Params) -> Option<JsonRpcRequestId>
+    where
+        R::Params: Serialize,
+    {
+        let id = JsonRpcRequestId::Integer(self.next_request_id.fetch_add(1, Ordering::Relaxed));
+        match serde_json::to_value(params) {
+            Ok(params) => {
+                self.send(&LSMessageRequest::new(id.clone(), R::METHOD, params));
+                Some(id)
+            }
+            Err(err) => {
+                error!("Couldn't serialize params of '{}': {err}", R::METHOD);
+                None
+            }
+        }
+    }
+
+    /// Writes a request, response, error or notification to the client
+    pub(crate) fn send<M: Serialize>(&self, message: &M) {
+        let message = match serde_json::to_string(message) {
+            Ok(message) => message,
+            Err(err) => {
+                error!("Couldn't serialize message: {err}");
+                return;
+            }
+        };
+        debug!("send: {:?}", message);
+        self.record(Direction::Out, || {
+            serde_json::from_str(&message).unwrap_or(serde_json::Value::Null)
+        });
+        let content_length = message.len();
+        let message = format!("Content-Length: {content_length}\r\n\r\n{message}");
+        // nothing may be logged while the lock is held, a layer forwarding
+        // logs to the client would otherwise deadlock trying to send them
+        let result = {
+            let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+            output
+                .write_all(message.as_bytes())
+                .and_then(|()| output.flush())
+        };
+        if let Err(err) = result {
+            error!("Couldn't write message: {err}");
+        }
+    }
+}
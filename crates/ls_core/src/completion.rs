@@ -0,0 +1,171 @@
+//! What can be typed at a position of a template
+
+use std::collections::HashSet;
+
+use lsp_types::{CompletionItem, CompletionItemKind, Documentation};
+
+use crate::{
+    Workspace,
+    controller::{camel_case, kebab_case},
+    directives::NG_DIRECTIVES,
+    document::Document,
+    embedded::template_at,
+    filters::{NG_FILTERS, usage},
+    navigation::find_registration_of,
+    registry::{Binding, Registration, RegistrationKind, Usage},
+    template::Template,
+};
+
+/// Completions at `offset` of the template `document`, or of a template
+/// embedded in the script `document`
+pub(crate) fn completions(
+    document: &Document,
+    offset: usize,
+    workspace: &Workspace,
+) -> Vec<CompletionItem> {
+    let Some(document) = &template_at(document, offset, workspace) else {
+        return vec![];
+    };
+    if is_tag_name_position(document.text(), offset) {
+        return registered(document, workspace, Usage::Element)
+            .map(|registration| registration_item(&registration))
+            .collect();
+    }
+    let template = Template::parse(document, workspace);
+    if template.is_filter_position(document.text(), offset) {
+        return filter_items(document, workspace);
+    }
+    let mut items = vec![];
+    if let Some(element) = template.attribute_position(offset) {
+        // the attribute being typed isn't set yet
+        let set: Vec<String> = template
+            .attributes_of(element)
+            .filter(|attribute| {
+                !(attribute.name_range.start <= offset && offset <= attribute.name_range.end)
+            })
+            .map(|attribute| camel_case(&attribute.directive_name()))
+            .collect();
+        if let Some((_, component)) =
+            find_registration_of(&element.name, Usage::Element, &document.uri, workspace)
+        {
+            items.extend(
+                component
+                    .bindings
+                    .iter()
+                    .filter(|binding| !set.contains(&binding.attribute))
+                    .map(|binding| CompletionItem {
+                        label: kebab_case(&binding.attribute),
+                        kind: Some(CompletionItemKind::PROPERTY),
+                        detail: Some(binding_detail(binding)),
+                        ..Default::default()
+                    }),
+            );
+        }
+        items.extend(
+            registered(document, workspace, Usage::Attribute)
+                .filter(|directive| !set.contains(&directive.name))
+                .map(|directive| registration_item(&directive)),
+        );
+        items.extend(
+            NG_DIRECTIVES
+                .iter()
+                .filter(|directive| !set.contains(&camel_case(directive.name)))
+                .map(|directive| CompletionItem {
+                    label: directive.name.to_owned(),
+                    kind: Some(CompletionItemKind::KEYWORD),
+                    detail: Some("AngularJS directive".to_owned()),
+                    documentation: Some(Documentation::String(directive.summary.to_owned())),
+                    ..Default::default()
+                }),
+        );
+    }
+    items
+}
+
+/// Whether `offset` is where the name of a start tag is typed: right after
+/// `<`, or in the name that follows it
+fn is_tag_name_position(text: &str, offset: usize) -> bool {
+    let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_' || c == ':';
+    let before = text[..offset].trim_end_matches(is_name);
+    before.ends_with('<')
+}
+
+/// The components and directives of the scripts near `document` that can be
+/// used as `usage`, once per name
+fn registered<'a>(
+    document: &'a Document,
+    workspace: &'a Workspace,
+    usage: Usage,
+) -> impl Iterator<Item = Registration> + 'a {
+    let mut seen = HashSet::new();
+    workspace
+        .scripts(&document.uri)
+        .into_iter()
+        .flat_map(|script| workspace.index(&script).registrations.clone())
+        .filter(move |registration| registration.allows(usage))
+        .filter(move |registration| seen.insert(registration.name.clone()))
+}
+
+/// The binding as it's declared in `bindings`, e.g. `user: '<userData'`
+fn binding_detail(binding: &Binding) -> String {
+    let alias = if binding.attribute == binding.name {
+        ""
+    } else {
+        &binding.attribute
+    };
+    format!("{}: '{}{alias}'", binding.name, binding.mode)
+}
+
+fn registration_item(registration: &Registration) -> CompletionItem {
+    let (kind, detail) = match registration.kind {
+        RegistrationKind::Component => (CompletionItemKind::CLASS, "component"),
+        RegistrationKind::Directive => (CompletionItemKind::FUNCTION, "directive"),
+        RegistrationKind::Controller => (CompletionItemKind::CLASS, "controller"),
+        RegistrationKind::Filter => {
+            return filter_item(&registration.name, &registration.parameters, None);
+        }
+    };
+    CompletionItem {
+        label: kebab_case(&registration.name),
+        kind: Some(kind),
+        detail: Some(detail.to_owned()),
+        ..Default::default()
+    }
+}
+
+/// The built-in filters, then those registered in the scripts near
+/// `document` under other names
+fn filter_items(document: &Document, workspace: &Workspace) -> Vec<CompletionItem> {
+    let mut seen: HashSet<String> = NG_FILTERS
+        .iter()
+        .map(|filter| filter.name.to_owned())
+        .collect();
+    let mut items: Vec<_> = NG_FILTERS
+        .iter()
+        .map(|filter| filter_item(filter.name, filter.parameters, Some(filter.summary)))
+        .collect();
+    items.extend(
+        workspace
+            .scripts(&document.uri)
+            .iter()
+            .flat_map(|script| workspace.index(script).registrations.clone())
+            .filter(|registration| registration.kind == RegistrationKind::Filter)
+            .filter(|filter| seen.insert(filter.name.clone()))
+            .map(|filter| registration_item(&filter)),
+    );
+    items
+}
+
+fn filter_item<S: AsRef<str>>(
+    name: &str,
+    parameters: &[S],
+    summary: Option<&str>,
+) -> CompletionItem {
+    CompletionItem {
+        label: name.to_owned(),
+        kind: Some(CompletionItemKind::FUNCTION),
+        detail: Some(usage(name, parameters)),
+        documentation: summary.map(|summary| Documentation::String(summary.to_owned())),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,271 @@
+//! Settings of a project.
+//!
+//! They're merged from, lowest priority first: the defaults, the client's
+//! `initializationOptions`, the `myls` section of its `workspace/configuration`
+//! and the project's `.mylsrc.json`. Each source only needs the settings it
+//! changes, e.g.
+//!
+//! ```json
+//! {
+//!     "controllerAliases": ["vm", "$ctrl"],
+//!     "exclude": ["**/node_modules/**", "dist/**"],
+//!     "diagnostics": { "unknown-directive": "off" }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lsp_types::DiagnosticSeverity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Name of the project config file, looked up in the root of the project
+pub const CONFIG_FILE_NAME: &str = ".mylsrc.json";
+
+/// Section of the client's settings the server reads
+pub const CONFIG_SECTION: &str = "myls";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Couldn't read '{}': {error}", .path.display())]
+    Io { path: PathBuf, error: io::Error },
+    #[error("Invalid settings: {0}")]
+    Invalid(#[from] serde_json::Error),
+    #[error("Invalid glob: {0}")]
+    Glob(#[from] globset::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    /// Names templates refer to their controller by, e.g. `vm.name`
+    pub controller_aliases: Vec<String>,
+    /// Endings of the names of controller files, tried in order.
+    /// `user-card.html` with `["Controller", ""]` looks for
+    /// `UserCardController.ts`, then `UserCard.ts`
+    pub controller_suffixes: Vec<String>,
+    pub interpolation: Interpolation,
+    /// Globs, relative to the project root, of the HTML files that are templates
+    pub include: Vec<String>,
+    /// Globs of files that aren't part of the project: templates that aren't
+    /// templates even if they're included, and scripts that are left alone
+    pub exclude: Vec<String>,
+    /// Directories, relative to the project root, that `templateUrl`s not
+    /// starting with `./` or `../` are resolved from, tried in order. With
+    /// `["src"]`, `app/foo.html` is `src/app/foo.html`
+    pub template_base_paths: Vec<String>,
+    /// Severity of diagnostics by their code, e.g. `"unknown-member": "warning"`
+    pub diagnostics: HashMap<String, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            controller_aliases: vec!["vm".to_owned()],
+            controller_suffixes: vec![
+                "Controller".to_owned(),
+                "Directive".to_owned(),
+                String::new(),
+            ],
+            interpolation: Interpolation::default(),
+            include: vec!["**/*.html".to_owned()],
+            exclude: vec!["**/node_modules/**".to_owned()],
+            template_base_paths: vec![".".to_owned()],
+            diagnostics: HashMap::new(),
+        }
+    }
+}
+
+/// What expressions are wrapped in inside text, as set with
+/// `$interpolateProvider.startSymbol()` and `endSymbol()`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Interpolation {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self {
+            start: "{{".to_owned(),
+            end: "}}".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+    /// The diagnostic isn't reported
+    Off,
+}
+
+impl Config {
+    /// The settings of each layer merged over the defaults, later layers
+    /// overriding earlier ones. Objects are merged key by key, anything else
+    /// is replaced
+    pub fn from_layers<'a>(
+        layers: impl IntoIterator<Item = &'a Value>,
+    ) -> Result<Self, ConfigError> {
+        let mut merged = serde_json::to_value(Self::default())?;
+        for layer in layers {
+            merge(&mut merged, layer);
+        }
+        let config: Self = serde_json::from_value(merged)?;
+        // bad globs are reported now rather than each time templates are listed
+        TemplateGlobs::new(&config)?;
+        Ok(config)
+    }
+
+    /// The settings in the config file at `path`, over the defaults
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_layers([&read_layer(path)?])
+    }
+
+    /// The settings in the config file of the project `start` is in: the
+    /// closest [`CONFIG_FILE_NAME`] in it or its parents. The defaults when
+    /// there's none
+    pub fn discover(start: &Path) -> Result<Self, ConfigError> {
+        let start = std::path::absolute(start).map_err(|error| ConfigError::Io {
+            path: start.to_owned(),
+            error,
+        })?;
+        match find_config_file(&start) {
+            Some(path) => Self::from_file(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The severity diagnostics with `code` are reported with,
+    /// `None` when they're turned off
+    pub(crate) fn severity(
+        &self,
+        code: &str,
+        default: DiagnosticSeverity,
+    ) -> Option<DiagnosticSeverity> {
+        match self.diagnostics.get(code) {
+            None => Some(default),
+            Some(Severity::Error) => Some(DiagnosticSeverity::ERROR),
+            Some(Severity::Warning) => Some(DiagnosticSeverity::WARNING),
+            Some(Severity::Information) => Some(DiagnosticSeverity::INFORMATION),
+            Some(Severity::Hint) => Some(DiagnosticSeverity::HINT),
+            Some(Severity::Off) => None,
+        }
+    }
+}
+
+/// Files found at the root of a project without a [`CONFIG_FILE_NAME`]
+const PROJECT_MARKERS: &[&str] = &["tsconfig.json", "package.json", ".git"];
+
+/// The closest [`CONFIG_FILE_NAME`] in `start`, an absolute path, or its parents
+fn find_config_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Root of the project `start` is in: the directory of the closest
+/// [`CONFIG_FILE_NAME`] in it or its parents, or else the closest one with one
+/// of the [`PROJECT_MARKERS`], or else the directory of `start` itself
+pub(crate) fn project_root(start: &Path) -> io::Result<PathBuf> {
+    let start = std::path::absolute(start)?;
+    if let Some(root) = find_config_file(&start).and_then(|path| path.parent().map(Path::to_owned))
+    {
+        return Ok(root);
+    }
+    let dir = if start.is_dir() {
+        start.as_path()
+    } else {
+        start.parent().unwrap_or(&start)
+    };
+    let root = dir
+        .ancestors()
+        .find(|dir| {
+            PROJECT_MARKERS
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+        })
+        .unwrap_or(dir);
+    Ok(root.to_owned())
+}
+
+/// The settings in the config file at `path`, as they're written
+pub(crate) fn read_layer(path: &Path) -> Result<Value, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|error| ConfigError::Io {
+        path: path.to_owned(),
+        error,
+    })?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn merge(base: &mut Value, layer: &Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        // settings the client doesn't have are sent as null
+        (_, Value::Null) => {}
+        (base, layer) => *base = layer.clone(),
+    }
+}
+
+/// The `include` and `exclude` globs of a [`Config`], compiled
+#[derive(Debug, Clone)]
+pub(crate) struct TemplateGlobs {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl TemplateGlobs {
+    pub(crate) fn new(config: &Config) -> Result<Self, globset::Error> {
+        let build = |globs: &[String]| {
+            globs
+                .iter()
+                .try_fold(GlobSetBuilder::new(), |mut builder, glob| {
+                    builder.add(Glob::new(glob)?);
+                    Ok::<_, globset::Error>(builder)
+                })?
+                .build()
+        };
+        Ok(Self {
+            include: build(&config.include)?,
+            exclude: build(&config.exclude)?,
+        })
+    }
+
+    /// Whether the file at `path`, relative to the project root, is a template
+    pub(crate) fn is_template(&self, path: &Path) -> bool {
+        self.include.is_match(path) && !self.is_excluded(path)
+    }
+
+    /// Whether the file at `path`, relative to the project root, is left out
+    /// of the project
+    pub(crate) fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude.is_match(path)
+    }
+}
+
+impl Default for TemplateGlobs {
+    fn default() -> Self {
+        Self::new(&Config::default()).expect("the default globs are valid")
+    }
+}
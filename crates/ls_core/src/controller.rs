@@ -0,0 +1,259 @@
+use std::ops::Range;
+
+use tree_sitter::{QueryCursor, StreamingIterator};
+
+use crate::{
+    Workspace,
+    document::Document,
+    embedded::embedded_binding,
+    registry::{RegistrationKind, ScriptQuery},
+    routes::routes_of,
+    uri::DocumentUri,
+};
+
+/// Members a template can reach through the controller: fields, methods,
+/// constructor parameter properties and properties assigned to `this`, or to
+/// a variable `this` was assigned to as in `var vm = this; vm.user = ...`.
+/// Whether `@object` is such a variable is checked afterwards
+const QUERY_CONTROLLER_MEMBERS: &str = r#"
+[
+    (public_field_definition name: (property_identifier) @member)
+    (method_definition name: (property_identifier) @member)
+    (required_parameter (accessibility_modifier) pattern: (identifier) @member)
+    (optional_parameter (accessibility_modifier) pattern: (identifier) @member)
+    (assignment_expression
+        left: (member_expression
+            object: (this)
+            property: (property_identifier) @member))
+    (variable_declarator name: (identifier) @alias value: (this))
+    (assignment_expression
+        left: (member_expression
+            object: (identifier) @object
+            property: (property_identifier) @aliased_member))
+]"#;
+
+static CONTROLLER_MEMBERS: ScriptQuery = ScriptQuery::new(QUERY_CONTROLLER_MEMBERS);
+
+/// A name declared in a document and the byte range of its declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Symbol {
+    pub(crate) name: String,
+    pub(crate) range: Range<usize>,
+}
+
+/// Controller files a template might be bound to, in the order they're tried.
+///
+/// With the suffixes `Controller`, `Directive` and none, `app/user-card.html`
+/// can be controlled by `app/UserCardController.ts`, `app/UserCardDirective.ts`
+/// or `app/UserCard.ts`
+pub(crate) fn get_controller_possible_uris(
+    uri: &DocumentUri,
+    suffixes: &[String],
+) -> Vec<DocumentUri> {
+    uri.path()
+        .strip_suffix(".html")
+        .and_then(|uri| uri.rsplit_once("/"))
+        .map(|(pre, filename)| {
+            let pascalified = pascal_case(filename);
+            suffixes
+                .iter()
+                .map(|ending| uri.with_path(format!("{pre}/{pascalified}{ending}.ts",)))
+                .collect()
+        })
+        .unwrap_or(vec![])
+}
+
+/// `user-card` as `UserCard`
+pub(crate) fn pascal_case(kebab: &str) -> String {
+    kebab
+        .split("-")
+        .map(|part| {
+            let mut chars = part.chars();
+            if let Some(first_char) = chars.next() {
+                let rest: String = chars.collect();
+                format!("{}{}", first_char.to_uppercase(), rest)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+/// `user-card` as `userCard`, the name a component or directive is
+/// registered with for an element or attribute of that name
+pub(crate) fn camel_case(kebab: &str) -> String {
+    let pascal = pascal_case(kebab);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first_char) => first_char.to_lowercase().chain(chars).collect(),
+        None => pascal,
+    }
+}
+
+/// `onSave` as `on-save`, the name of the attribute for a binding or directive
+pub(crate) fn kebab_case(camel: &str) -> String {
+    let mut kebab = String::with_capacity(camel.len());
+    for c in camel.chars() {
+        if c.is_uppercase() {
+            if !kebab.is_empty() {
+                kebab.push('-');
+            }
+            kebab.extend(c.to_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+/// What a template is bound to
+#[derive(Debug, Default)]
+pub(crate) struct TemplateBinding {
+    pub(crate) controller: Option<Document>,
+    /// Names the template calls the controller: those of the config, and
+    /// the `controllerAs` of the routes showing it
+    pub(crate) aliases: Vec<String>,
+    /// Members the controller is given rather than declares, with the script
+    /// declaring each: the keys of the `resolve` of the routes showing the
+    /// template, or the bindings of the component it's embedded in
+    pub(crate) given: Vec<(Document, Symbol)>,
+}
+
+/// What the template `template` is bound to, see [`embedded_binding`] for
+/// those embedded in scripts and [`file_binding`] for the others
+pub(crate) fn template_binding(template: &Document, workspace: &Workspace) -> TemplateBinding {
+    if workspace.is_script(&template.uri) {
+        embedded_binding(template, workspace)
+    } else {
+        file_binding(&template.uri, workspace)
+    }
+}
+
+/// What the template at `uri` is bound to. The controller is that of a route
+/// showing the template, or else the first of its possible files that's open
+/// or exists on disk
+pub(crate) fn file_binding(uri: &DocumentUri, workspace: &Workspace) -> TemplateBinding {
+    let routes = routes_of(uri, workspace);
+    let mut aliases = workspace.config.controller_aliases.clone();
+    for alias in routes
+        .iter()
+        .filter_map(|(_, route)| route.controller_as.clone())
+    {
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    let controller = routes
+        .iter()
+        .filter_map(|(_, route)| route.controller.as_deref())
+        .find_map(|name| named_controller(name, uri, workspace))
+        .or_else(|| {
+            get_controller_possible_uris(uri, &workspace.config.controller_suffixes)
+                .iter()
+                .find_map(|uri| workspace.documents.get_or_read(uri))
+        });
+    let given = routes
+        .iter()
+        .flat_map(|(script, route)| {
+            route
+                .resolve
+                .iter()
+                .map(|key| (script.clone(), key.clone()))
+        })
+        .collect();
+    TemplateBinding {
+        controller,
+        aliases,
+        given,
+    }
+}
+
+/// The controller of the template file at `uri`, see [`file_binding`]
+pub(crate) fn find_controller(uri: &DocumentUri, workspace: &Workspace) -> Option<Document> {
+    file_binding(uri, workspace).controller
+}
+
+/// The script implementing the controller `name`: the one declaring the
+/// class it's registered with, or else the one registering it
+pub(crate) fn named_controller(
+    name: &str,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<Document> {
+    let scripts = workspace.scripts(near);
+    let registered = scripts.iter().find_map(|script| {
+        workspace
+            .index(script)
+            .registrations
+            .iter()
+            .find(|registration| {
+                registration.kind == RegistrationKind::Controller && registration.name == name
+            })
+            .map(|registration| (script, registration.controller.clone()))
+    });
+    let class = registered
+        .as_ref()
+        .and_then(|(_, class)| class.as_deref())
+        .unwrap_or(name);
+    scripts
+        .iter()
+        .find(|script| workspace.index(script).find_class(class).is_some())
+        .or(registered.map(|(script, _)| script))
+        .cloned()
+}
+
+/// Members declared in a controller document.
+/// A name declared more than once is listed at each declaration
+pub(crate) fn controller_members(document: &Document) -> Vec<Symbol> {
+    let language = tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into();
+    let mut parser = tree_sitter::Parser::new();
+    if parser.set_language(&language).is_err() {
+        return vec![];
+    }
+    let Some(tree) = parser.parse(document.text(), None) else {
+        return vec![];
+    };
+    let Some(query) = CONTROLLER_MEMBERS.get(&language) else {
+        return vec![];
+    };
+    let text = document.text();
+    let alias_index = query.capture_index_for_name("alias");
+    let object_index = query.capture_index_for_name("object");
+    let mut members = vec![];
+    let mut aliases = vec![];
+    // members assigned to an identifier, kept till the aliases of `this` are known
+    let mut aliased_members = vec![];
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), text.as_bytes());
+    while let Some(m) = matches.next() {
+        let object = m
+            .captures
+            .iter()
+            .find(|capture| Some(capture.index) == object_index)
+            .map(|capture| &text[capture.node.byte_range()]);
+        for capture in m.captures {
+            let node = capture.node;
+            let symbol = Symbol {
+                name: text[node.byte_range()].to_owned(),
+                range: node.byte_range(),
+            };
+            if Some(capture.index) == alias_index {
+                aliases.push(symbol.name);
+            } else if Some(capture.index) == object_index {
+                continue;
+            } else if let Some(object) = object {
+                aliased_members.push((object, symbol));
+            } else {
+                members.push(symbol);
+            }
+        }
+    }
+    members.extend(
+        aliased_members
+            .into_iter()
+            .filter(|(object, _)| aliases.iter().any(|alias| alias == object))
+            .map(|(_, member)| member),
+    );
+    members.sort_by_key(|member| member.range.start);
+    members
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::{
+    Workspace,
+    controller::{
+        camel_case, controller_members, get_controller_possible_uris, kebab_case, named_controller,
+        template_binding,
+    },
+    directives::{is_html_attribute, is_unknown_ng_directive},
+    document::Document,
+    embedded::{EmbeddedController, embedded_templates, find_embedded},
+    filters::ng_filter,
+    registry::{Registration, RegistrationKind, Usage},
+    template::Template,
+};
+
+/// What diagnostics say they come from
+const SOURCE: &str = "myls";
+
+/// `vm.member` where the controller has no `member`
+const UNKNOWN_MEMBER: &str = "unknown-member";
+/// The template uses `vm` but none of the files its controller could be in
+/// exist, or an `ng-controller` names a controller that isn't found
+const UNRESOLVED_CONTROLLER: &str = "unresolved-controller";
+/// An `ng-` attribute that's neither an AngularJS directive nor one the
+/// project registers
+const UNKNOWN_DIRECTIVE: &str = "unknown-directive";
+/// An attribute of a component's element that's none of its bindings
+const UNKNOWN_BINDING: &str = "unknown-binding";
+/// A component's element without one of the bindings it requires
+const MISSING_BINDING: &str = "missing-binding";
+/// A filter that's neither built in nor registered
+const UNKNOWN_FILTER: &str = "unknown-filter";
+
+/// Problems found in the template `document`, or in the templates embedded in
+/// the script `document`, checked against the controller each is bound to
+pub(crate) fn template_diagnostics(document: &Document, workspace: &Workspace) -> Vec<Diagnostic> {
+    if workspace.is_script(&document.uri) {
+        return embedded_templates(document)
+            .iter()
+            .flat_map(|template| diagnostics_of(&template.document, workspace))
+            .collect();
+    }
+    diagnostics_of(document, workspace)
+}
+
+fn diagnostics_of(document: &Document, workspace: &Workspace) -> Vec<Diagnostic> {
+    let config = &workspace.config;
+    let mut diagnostics = vec![];
+    let mut push = |range: std::ops::Range<usize>, severity, code: &str, message| {
+        let Some(severity) = config.severity(code, severity) else {
+            return;
+        };
+        diagnostics.push(Diagnostic {
+            range: document.range(range.start, range.end, workspace.encoding),
+            severity: Some(severity),
+            code: Some(NumberOrString::String(code.to_owned())),
+            source: Some(SOURCE.to_owned()),
+            message,
+            ..Default::default()
+        });
+    };
+
+    let template = Template::parse(document, workspace);
+    // projects register `ng` directives of their own too, which are only
+    // looked up when AngularJS doesn't know the attribute
+    let unknown_ng_attributes: Vec<_> = template
+        .attributes
+        .iter()
+        .filter(|attribute| is_unknown_ng_directive(&attribute.directive_name()))
+        .collect();
+    if !unknown_ng_attributes.is_empty() {
+        let registrations: Vec<Registration> = workspace
+            .scripts(&document.uri)
+            .iter()
+            .flat_map(|script| workspace.index(script).registrations.clone())
+            .filter(|registration| registration.allows(Usage::Attribute))
+            .collect();
+        for attribute in unknown_ng_attributes {
+            let name = camel_case(&attribute.directive_name());
+            if registrations
+                .iter()
+                .any(|registration| registration.name == name)
+            {
+                continue;
+            }
+            push(
+                attribute.name_range.clone(),
+                DiagnosticSeverity::WARNING,
+                UNKNOWN_DIRECTIVE,
+                format!("'{}' isn't a known AngularJS directive", attribute.name),
+            );
+        }
+    }
+
+    // only custom elements are looked up, not to go through the scripts
+    // for templates that use none
+    let custom_elements: Vec<_> = template
+        .elements
+        .iter()
+        .filter(|element| element.name.contains('-'))
+        .collect();
+    if !custom_elements.is_empty() {
+        let registrations: Vec<Registration> = workspace
+            .scripts(&document.uri)
+            .iter()
+            .flat_map(|script| workspace.index(script).registrations.clone())
+            .collect();
+        for element in custom_elements {
+            let name = camel_case(&element.name);
+            let Some(component) = registrations.iter().find(|registration| {
+                registration.kind == RegistrationKind::Component && registration.name == name
+            }) else {
+                continue;
+            };
+            let attributes: Vec<_> = template.attributes_of(element).collect();
+            for attribute in &attributes {
+                let directive_name = attribute.directive_name();
+                let binding = camel_case(&directive_name);
+                let is_known = component
+                    .bindings
+                    .iter()
+                    .any(|known| known.attribute == binding)
+                    || directive_name.starts_with("ng-")
+                    || is_html_attribute(&attribute.name)
+                    || registrations.iter().any(|registration| {
+                        registration.name == binding && registration.allows(Usage::Attribute)
+                    });
+                if !is_known {
+                    push(
+                        attribute.name_range.clone(),
+                        DiagnosticSeverity::WARNING,
+                        UNKNOWN_BINDING,
+                        format!(
+                            "'{}' isn't a binding of the component '{name}'",
+                            attribute.name
+                        ),
+                    );
+                }
+            }
+            for binding in component
+                .bindings
+                .iter()
+                .filter(|binding| binding.is_required())
+            {
+                let is_set = attributes
+                    .iter()
+                    .any(|attribute| camel_case(&attribute.directive_name()) == binding.attribute);
+                if !is_set {
+                    push(
+                        element.name_range.clone(),
+                        DiagnosticSeverity::WARNING,
+                        MISSING_BINDING,
+                        format!(
+                            "'{}' is missing the binding '{}' of the component '{name}'",
+                            element.name,
+                            kebab_case(&binding.attribute)
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    // likewise, only filters that aren't built in are looked up
+    let filters: Vec<_> = template
+        .filters(document.text())
+        .filter(|name| ng_filter(&document.text()[name.clone()]).is_none())
+        .collect();
+    if !filters.is_empty() {
+        let registered: Vec<String> = workspace
+            .scripts(&document.uri)
+            .iter()
+            .flat_map(|script| workspace.index(script).registrations.clone())
+            .filter(|registration| registration.kind == RegistrationKind::Filter)
+            .map(|filter| filter.name)
+            .collect();
+        for range in filters {
+            let name = &document.text()[range.clone()];
+            if !registered.iter().any(|filter| filter == name) {
+                push(
+                    range,
+                    DiagnosticSeverity::WARNING,
+                    UNKNOWN_FILTER,
+                    format!("'{name}' isn't a known filter"),
+                );
+            }
+        }
+    }
+
+    // the controllers of `ng-controller`s, by name, with their members
+    let mut named = HashMap::new();
+    for scope in &template.scopes {
+        if named.contains_key(&scope.name) {
+            continue;
+        }
+        let controller =
+            named_controller(&scope.name, &document.uri, workspace).map(|controller| {
+                let members = controller_members(&controller);
+                (controller, members)
+            });
+        if controller.is_none() {
+            push(
+                scope.name_range.clone(),
+                DiagnosticSeverity::WARNING,
+                UNRESOLVED_CONTROLLER,
+                format!("No controller named '{}' found", scope.name),
+            );
+        }
+        named.insert(scope.name.clone(), controller);
+    }
+
+    let binding = template_binding(document, workspace);
+    let (scoped, references): (Vec<_>, Vec<_>) = template
+        .controller_references(document, &binding.aliases)
+        .into_iter()
+        .partition(|reference| reference.controller.is_some());
+    for reference in &scoped {
+        if let Some(Some((controller, members))) = reference
+            .controller
+            .as_ref()
+            .and_then(|name| named.get(name))
+            && !members.iter().any(|member| member.name == reference.name)
+        {
+            push(
+                reference.range.clone(),
+                DiagnosticSeverity::ERROR,
+                UNKNOWN_MEMBER,
+                unknown_member(&reference.name, controller),
+            );
+        }
+    }
+
+    let Some(first_reference) = references.first() else {
+        return diagnostics;
+    };
+    match binding.controller {
+        Some(controller) => {
+            let members = controller_members(&controller);
+            // the keys of a route's `resolve` are given to the controller,
+            // and so are a component's bindings
+            let is_member = |name: &str| {
+                members.iter().any(|member| member.name == name)
+                    || binding.given.iter().any(|(_, key)| key.name == name)
+            };
+            for reference in &references {
+                if !is_member(&reference.name) {
+                    push(
+                        reference.range.clone(),
+                        DiagnosticSeverity::ERROR,
+                        UNKNOWN_MEMBER,
+                        unknown_member(&reference.name, &controller),
+                    );
+                }
+            }
+        }
+        None if workspace.is_script(&document.uri) => {
+            match find_embedded(document, workspace).and_then(|(_, embedded)| embedded.controller) {
+                Some(EmbeddedController::Named(name)) => push(
+                    first_reference.expression_range.clone(),
+                    DiagnosticSeverity::WARNING,
+                    UNRESOLVED_CONTROLLER,
+                    format!("No controller named '{name}' found"),
+                ),
+                // without a controller, only the bindings are there
+                _ => {
+                    for reference in &references {
+                        if !binding
+                            .given
+                            .iter()
+                            .any(|(_, key)| key.name == reference.name)
+                        {
+                            push(
+                                reference.range.clone(),
+                                DiagnosticSeverity::ERROR,
+                                UNKNOWN_MEMBER,
+                                format!("'{}' isn't a binding of the component", reference.name),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        None => {
+            let tried = get_controller_possible_uris(&document.uri, &config.controller_suffixes)
+                .iter()
+                .map(|uri| format!("'{}'", file_name(uri.path())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            push(
+                first_reference.expression_range.clone(),
+                DiagnosticSeverity::WARNING,
+                UNRESOLVED_CONTROLLER,
+                format!("No controller found for this template, tried {tried}"),
+            );
+        }
+    }
+    diagnostics
+}
+
+fn unknown_member(name: &str, controller: &Document) -> String {
+    format!(
+        "'{name}' doesn't exist on the controller '{}'",
+        file_name(controller.uri.path())
+    )
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
@@ -0,0 +1,586 @@
+use std::ops::Range;
+
+use ValueKind::{Expression, Interpolation, MicroSyntax, Text};
+
+/// What the value of a directive's attribute holds, and so where expressions
+/// are to be found in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueKind {
+    /// An expression as a whole, `ng-if="vm.ready"`
+    Expression,
+    /// Text with interpolated expressions, `ng-href="/users/{{ vm.id }}"`
+    Interpolation,
+    /// Expressions among keywords and local names, `ng-repeat="user in vm.users"`
+    MicroSyntax,
+    /// Plain text, a name or a flag, `ng-app="app"`
+    Text,
+}
+
+/// A directive that ships with AngularJS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NgDirective {
+    /// The normalized attribute name, e.g. `ng-if`
+    pub(crate) name: &'static str,
+    pub(crate) value: ValueKind,
+    /// What it does, in a sentence
+    pub(crate) summary: &'static str,
+}
+
+impl NgDirective {
+    /// Markdown documenting the directive, as shown on hover
+    pub(crate) fn documentation(&self) -> String {
+        let value = match self.value {
+            ValueKind::Expression => "an expression",
+            ValueKind::Interpolation => "text with `{{ }}` interpolations",
+            ValueKind::MicroSyntax => match self.name {
+                "ng-controller" => "`Controller as alias`",
+                "ng-options" => "`label for value in array track by expression`",
+                _ => "`item in collection track by expression`",
+            },
+            ValueKind::Text => "plain text",
+        };
+        format!(
+            "**{}** (AngularJS directive)\n\n{}\n\nValue: {value}",
+            self.name, self.summary
+        )
+    }
+}
+
+const fn ng(name: &'static str, value: ValueKind, summary: &'static str) -> NgDirective {
+    NgDirective {
+        name,
+        value,
+        summary,
+    }
+}
+
+/// Directives that ship with AngularJS, by their normalized attribute name.
+/// Those of its optional modules, e.g. ngRoute's `ng-view`, come last
+pub(crate) const NG_DIRECTIVES: &[NgDirective] = &[
+    ng(
+        "ng-app",
+        Text,
+        "Auto-bootstraps the application on this element.",
+    ),
+    ng(
+        "ng-bind",
+        Expression,
+        "Replaces the text content of the element with the value of an expression.",
+    ),
+    ng(
+        "ng-bind-html",
+        Expression,
+        "Replaces the content of the element with the sanitized HTML an expression evaluates to.",
+    ),
+    ng(
+        "ng-bind-template",
+        Interpolation,
+        "Replaces the text content of the element with an interpolated template.",
+    ),
+    ng(
+        "ng-blur",
+        Expression,
+        "Evaluates an expression when the element loses focus.",
+    ),
+    ng(
+        "ng-change",
+        Expression,
+        "Evaluates an expression when the user changes the value of an input.",
+    ),
+    ng(
+        "ng-checked",
+        Expression,
+        "Sets the `checked` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-class",
+        Expression,
+        "Adds the classes an expression evaluates to: a string, an array or a map of class names to conditions.",
+    ),
+    ng(
+        "ng-class-even",
+        Expression,
+        "Like `ng-class`, on the even rows of an `ng-repeat`.",
+    ),
+    ng(
+        "ng-class-odd",
+        Expression,
+        "Like `ng-class`, on the odd rows of an `ng-repeat`.",
+    ),
+    ng(
+        "ng-click",
+        Expression,
+        "Evaluates an expression when the element is clicked.",
+    ),
+    ng(
+        "ng-cloak",
+        Text,
+        "Hides the element until AngularJS has compiled it.",
+    ),
+    ng(
+        "ng-controller",
+        MicroSyntax,
+        "Attaches a controller to the element, as `Controller as alias`.",
+    ),
+    ng("ng-copy", Expression, "Evaluates an expression on copy."),
+    ng(
+        "ng-csp",
+        Text,
+        "Adapts AngularJS to a Content Security Policy.",
+    ),
+    ng("ng-cut", Expression, "Evaluates an expression on cut."),
+    ng(
+        "ng-dblclick",
+        Expression,
+        "Evaluates an expression when the element is double-clicked.",
+    ),
+    ng(
+        "ng-disabled",
+        Expression,
+        "Sets the `disabled` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-focus",
+        Expression,
+        "Evaluates an expression when the element gains focus.",
+    ),
+    ng(
+        "ng-form",
+        Interpolation,
+        "Nests a form, to validate a group of controls.",
+    ),
+    ng(
+        "ng-hide",
+        Expression,
+        "Hides the element when the expression is truthy.",
+    ),
+    ng(
+        "ng-href",
+        Interpolation,
+        "Sets `href` to an interpolated URL once it's evaluated.",
+    ),
+    ng(
+        "ng-if",
+        Expression,
+        "Adds the element to the DOM only while the expression is truthy.",
+    ),
+    ng(
+        "ng-include",
+        Expression,
+        "Fetches, compiles and includes the template the expression evaluates to the URL of.",
+    ),
+    ng(
+        "ng-init",
+        Expression,
+        "Evaluates an expression in the current scope once, on initialization.",
+    ),
+    ng(
+        "ng-jq",
+        Text,
+        "Names the jQuery library AngularJS should use.",
+    ),
+    ng(
+        "ng-keydown",
+        Expression,
+        "Evaluates an expression on keydown.",
+    ),
+    ng(
+        "ng-keypress",
+        Expression,
+        "Evaluates an expression on keypress.",
+    ),
+    ng("ng-keyup", Expression, "Evaluates an expression on keyup."),
+    ng(
+        "ng-list",
+        Text,
+        "Converts between a delimited string and an array of strings.",
+    ),
+    ng(
+        "ng-maxlength",
+        Expression,
+        "Validates that the value isn't longer than the given length.",
+    ),
+    ng(
+        "ng-minlength",
+        Expression,
+        "Validates that the value isn't shorter than the given length.",
+    ),
+    ng(
+        "ng-model",
+        Expression,
+        "Binds the value of an input, select or textarea to a property of the scope.",
+    ),
+    ng(
+        "ng-model-options",
+        Expression,
+        "Tunes how `ng-model` updates: debounce, update-on events, time zone.",
+    ),
+    ng(
+        "ng-mousedown",
+        Expression,
+        "Evaluates an expression on mousedown.",
+    ),
+    ng(
+        "ng-mouseenter",
+        Expression,
+        "Evaluates an expression on mouseenter.",
+    ),
+    ng(
+        "ng-mouseleave",
+        Expression,
+        "Evaluates an expression on mouseleave.",
+    ),
+    ng(
+        "ng-mousemove",
+        Expression,
+        "Evaluates an expression on mousemove.",
+    ),
+    ng(
+        "ng-mouseover",
+        Expression,
+        "Evaluates an expression on mouseover.",
+    ),
+    ng(
+        "ng-mouseup",
+        Expression,
+        "Evaluates an expression on mouseup.",
+    ),
+    ng(
+        "ng-non-bindable",
+        Text,
+        "Leaves the contents of the element uncompiled.",
+    ),
+    ng(
+        "ng-open",
+        Expression,
+        "Sets the `open` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-options",
+        MicroSyntax,
+        "Generates the options of a select from an array or object.",
+    ),
+    ng("ng-paste", Expression, "Evaluates an expression on paste."),
+    ng(
+        "ng-pattern",
+        Expression,
+        "Validates that the value matches a regular expression.",
+    ),
+    ng(
+        "ng-pluralize",
+        Text,
+        "Displays messages according to the en-US pluralization rules.",
+    ),
+    ng(
+        "ng-readonly",
+        Expression,
+        "Sets the `readonly` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-ref",
+        Expression,
+        "Publishes the controller of a component or directive on the scope.",
+    ),
+    ng(
+        "ng-ref-read",
+        Text,
+        "Which controller or element `ng-ref` publishes.",
+    ),
+    ng(
+        "ng-repeat",
+        MicroSyntax,
+        "Instantiates the element once per item of a collection.",
+    ),
+    ng(
+        "ng-repeat-end",
+        Text,
+        "Ends a range of elements repeated by `ng-repeat-start`.",
+    ),
+    ng(
+        "ng-repeat-start",
+        MicroSyntax,
+        "Like `ng-repeat`, over the elements up to `ng-repeat-end`.",
+    ),
+    ng(
+        "ng-required",
+        Expression,
+        "Sets the `required` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-selected",
+        Expression,
+        "Sets the `selected` attribute when the expression is truthy.",
+    ),
+    ng(
+        "ng-show",
+        Expression,
+        "Shows the element only when the expression is truthy.",
+    ),
+    ng(
+        "ng-src",
+        Interpolation,
+        "Sets `src` to an interpolated URL once it's evaluated.",
+    ),
+    ng(
+        "ng-srcset",
+        Interpolation,
+        "Sets `srcset` to an interpolated value once it's evaluated.",
+    ),
+    ng(
+        "ng-strict-di",
+        Text,
+        "Makes the injector fail on functions without explicit annotations.",
+    ),
+    ng(
+        "ng-style",
+        Expression,
+        "Sets the inline styles an expression evaluates to.",
+    ),
+    ng(
+        "ng-submit",
+        Expression,
+        "Evaluates an expression when the form is submitted.",
+    ),
+    ng(
+        "ng-switch",
+        Expression,
+        "Shows the child matching the value of the expression.",
+    ),
+    ng(
+        "ng-switch-default",
+        Text,
+        "The child `ng-switch` shows when no `ng-switch-when` matches.",
+    ),
+    ng(
+        "ng-switch-when",
+        Text,
+        "The child `ng-switch` shows for this value.",
+    ),
+    ng(
+        "ng-switch-when-separator",
+        Text,
+        "Splits `ng-switch-when` into several values.",
+    ),
+    ng(
+        "ng-transclude",
+        Text,
+        "Marks where the transcluded content of a directive goes.",
+    ),
+    ng(
+        "ng-true-value",
+        Expression,
+        "The value `ng-model` is set to when the checkbox is checked.",
+    ),
+    ng(
+        "ng-false-value",
+        Expression,
+        "The value `ng-model` is set to when the checkbox is unchecked.",
+    ),
+    ng(
+        "ng-trim",
+        Text,
+        "Whether `ng-model` trims the value of the input, on by default.",
+    ),
+    ng(
+        "ng-value",
+        Expression,
+        "Binds the value of an input or option to an expression.",
+    ),
+    ng(
+        "ng-animate-children",
+        Text,
+        "ngAnimate: lets the children of the element animate while it does.",
+    ),
+    ng(
+        "ng-animate-swap",
+        Expression,
+        "ngAnimate: swaps the element for a new one whenever an expression changes.",
+    ),
+    ng(
+        "ng-message",
+        Text,
+        "ngMessages: a message shown when the error it names is set.",
+    ),
+    ng(
+        "ng-message-default",
+        Text,
+        "ngMessages: a message shown when none of the others apply.",
+    ),
+    ng(
+        "ng-message-exp",
+        Expression,
+        "ngMessages: a message shown when the error an expression evaluates to is set.",
+    ),
+    ng(
+        "ng-messages",
+        Expression,
+        "ngMessages: shows the messages of the errors of the object an expression evaluates to, e.g. `form.email.$error`.",
+    ),
+    ng(
+        "ng-messages-include",
+        Text,
+        "ngMessages: includes the messages of another template.",
+    ),
+    ng(
+        "ng-messages-multiple",
+        Text,
+        "ngMessages: shows every message that applies instead of the first one.",
+    ),
+    ng(
+        "ng-swipe-left",
+        Expression,
+        "ngTouch: evaluates an expression when the element is swiped left.",
+    ),
+    ng(
+        "ng-swipe-right",
+        Expression,
+        "ngTouch: evaluates an expression when the element is swiped right.",
+    ),
+    ng(
+        "ng-view",
+        Text,
+        "ngRoute: shows the template of the current route.",
+    ),
+];
+
+/// Prefixes of attributes AngularJS binds to any attribute, property or event,
+/// e.g. `ng-attr-title`
+const NG_BINDING_PREFIXES: &[&str] = &["ng-attr-", "ng-prop-", "ng-on-"];
+
+/// The built-in directive `name`, a normalized attribute name, stands for
+pub(crate) fn ng_directive(name: &str) -> Option<&'static NgDirective> {
+    NG_DIRECTIVES
+        .iter()
+        .find(|directive| directive.name == name)
+}
+
+/// The prefix of `ng-attr-title` and the like, when `name` has one
+fn ng_binding_prefix(name: &str) -> Option<&'static str> {
+    NG_BINDING_PREFIXES.iter().copied().find(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|rest| !rest.is_empty())
+    })
+}
+
+/// Whether `name`, a normalized attribute name, is an `ng-` attribute
+/// AngularJS doesn't know about
+pub(crate) fn is_unknown_ng_directive(name: &str) -> bool {
+    name.starts_with("ng-") && ng_directive(name).is_none() && ng_binding_prefix(name).is_none()
+}
+
+/// What the value of the `ng-` attribute `name` holds, if AngularJS knows it
+pub(crate) fn ng_value_kind(name: &str) -> Option<ValueKind> {
+    match ng_binding_prefix(name) {
+        // `ng-attr-` sets the attribute to the interpolated text
+        Some("ng-attr-") => Some(Interpolation),
+        Some(_) => Some(Expression),
+        None => ng_directive(name).map(|directive| directive.value),
+    }
+}
+
+/// Keywords splitting the value of a micro-syntax directive into parts
+const MICRO_SYNTAX_KEYWORDS: &[&str] = &["track by", "group by", "disable when", "for", "in", "as"];
+
+/// The expressions in `value`, the value of the micro-syntax directive `name`,
+/// as ranges of `value`. Local names, aliases and controller names aren't
+/// expressions:
+/// - `ng-repeat="(key, user) in vm.users | orderBy:'name' as shown track by user.id"`
+///   has the collection, with its filters, and the tracking expression
+/// - `ng-options="user.name group by user.team for user in vm.users"` has every
+///   part but the local names between `for` and `in`
+/// - `ng-controller="UserController as vm"` has none
+pub(crate) fn micro_syntax_expressions(name: &str, value: &str) -> Vec<Range<usize>> {
+    let mut parts = vec![];
+    let mut keyword = None;
+    let mut start = 0;
+    let mut at = 0;
+    while at < value.len() {
+        let found = (at == 0 || value[..at].ends_with(char::is_whitespace))
+            .then(|| {
+                MICRO_SYNTAX_KEYWORDS.iter().find(|candidate| {
+                    value[at..].starts_with(**candidate)
+                        && value[at + candidate.len()..]
+                            .chars()
+                            .next()
+                            .is_none_or(char::is_whitespace)
+                })
+            })
+            .flatten();
+        match found {
+            Some(found) => {
+                parts.push((keyword, start..at));
+                keyword = Some(*found);
+                at += found.len();
+                start = at;
+            }
+            None => at += value[at..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    parts.push((keyword, start..value.len()));
+
+    let is_expression = |keyword| match name {
+        "ng-repeat" | "ng-repeat-start" => matches!(keyword, Some("in" | "track by")),
+        // the locals sit between `for` and `in`
+        "ng-options" => keyword != Some("for"),
+        _ => false,
+    };
+    let mut expressions = vec![];
+    for (keyword, range) in parts {
+        let range = trim(value, range);
+        if is_expression(keyword) && !range.is_empty() {
+            expressions.push(range);
+        }
+    }
+    expressions
+}
+
+/// `range` of `text` without surrounding whitespace
+fn trim(text: &str, range: Range<usize>) -> Range<usize> {
+    let part = &text[range.clone()];
+    let start = range.start + (part.len() - part.trim_start().len());
+    let end = range.end - (part.len() - part.trim_end().len());
+    start..end.max(start)
+}
+
+/// Attributes any HTML element can have, which components don't declare as
+/// bindings
+const HTML_GLOBAL_ATTRIBUTES: &[&str] = &[
+    "accesskey",
+    "autocapitalize",
+    "autofocus",
+    "class",
+    "contenteditable",
+    "dir",
+    "draggable",
+    "enterkeyhint",
+    "hidden",
+    "id",
+    "inert",
+    "inputmode",
+    "is",
+    "itemid",
+    "itemprop",
+    "itemref",
+    "itemscope",
+    "itemtype",
+    "lang",
+    "nonce",
+    "part",
+    "popover",
+    "role",
+    "slot",
+    "spellcheck",
+    "style",
+    "tabindex",
+    "title",
+    "translate",
+];
+
+/// Whether the attribute `name` is a global HTML attribute, including
+/// `aria-*` and event handlers such as `onclick`
+pub(crate) fn is_html_attribute(name: &str) -> bool {
+    let name = name.to_lowercase();
+    HTML_GLOBAL_ATTRIBUTES.contains(&name.as_str())
+        || name.starts_with("aria-")
+        || (name.starts_with("on") && !name.contains('-'))
+}
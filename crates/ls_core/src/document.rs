@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fs};
+
+use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+use crate::{
+    line_index::{LineIndex, PositionEncoding},
+    uri::DocumentUri,
+    workspace::InvalidUri,
+};
+
+/// A text document along with its line index
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub(crate) uri: DocumentUri,
+    text: String,
+    line_index: LineIndex,
+    /// Version sent by the client, `None` for documents read from disk
+    pub(crate) version: Option<i32>,
+}
+
+impl Document {
+    pub(crate) fn new(uri: DocumentUri, text: String, version: Option<i32>) -> Self {
+        let line_index = LineIndex::new(&text);
+        Self {
+            uri,
+            text,
+            line_index,
+            version,
+        }
+    }
+
+    /// The URI of the document, as it's sent to the client. The query and
+    /// fragment are kept as they were received, and may not make a valid URI
+    pub fn uri(&self) -> Result<lsp_types::Uri, InvalidUri> {
+        lsp_types::Uri::try_from(&self.uri).map_err(|_err| InvalidUri(self.uri.to_string()))
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Version sent by the client, `None` for documents read from disk
+    pub fn version(&self) -> Option<i32> {
+        self.version
+    }
+
+    pub(crate) fn offset(&self, position: Position, encoding: PositionEncoding) -> usize {
+        self.line_index.offset(&self.text, position, encoding)
+    }
+
+    pub(crate) fn range(&self, start: usize, end: usize, encoding: PositionEncoding) -> Range {
+        self.line_index.range(&self.text, start, end, encoding)
+    }
+
+    /// Applies a `textDocument/didChange` content change.
+    /// A change without a range replaces the whole text
+    fn apply_change(&mut self, change: TextDocumentContentChangeEvent, encoding: PositionEncoding) {
+        match change.range {
+            Some(range) => {
+                let start = self.offset(range.start, encoding);
+                let end = self.offset(range.end, encoding).max(start);
+                self.text.replace_range(start..end, &change.text);
+            }
+            None => self.text = change.text,
+        }
+        self.line_index = LineIndex::new(&self.text);
+    }
+}
+
+/// Documents currently opened by the client, ordered by URI so that going
+/// through them, e.g. to publish diagnostics, happens in the same order every time.
+///
+/// This is the only place documents with a non-file scheme can be read from
+#[derive(Debug, Default)]
+pub(crate) struct DocumentStore {
+    documents: BTreeMap<DocumentUri, Document>,
+}
+
+impl DocumentStore {
+    pub(crate) fn open(&mut self, uri: DocumentUri, text: String, version: i32) {
+        let document = Document::new(uri.clone(), text, Some(version));
+        self.documents.insert(uri, document);
+    }
+
+    pub(crate) fn change(
+        &mut self,
+        uri: &DocumentUri,
+        version: i32,
+        changes: Vec<TextDocumentContentChangeEvent>,
+        encoding: PositionEncoding,
+    ) {
+        if let Some(document) = self.documents.get_mut(uri) {
+            for change in changes {
+                document.apply_change(change, encoding);
+            }
+            document.version = Some(version);
+        }
+    }
+
+    pub(crate) fn close(&mut self, uri: &DocumentUri) {
+        self.documents.remove(uri);
+    }
+
+    /// Removes every open document, e.g. to hand them over to another store
+    pub(crate) fn take_all(&mut self) -> impl Iterator<Item = Document> + use<> {
+        std::mem::take(&mut self.documents).into_values()
+    }
+
+    /// Adds an open document taken from another store
+    pub(crate) fn insert(&mut self, document: Document) {
+        self.documents.insert(document.uri.clone(), document);
+    }
+
+    pub(crate) fn get(&self, uri: &DocumentUri) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Document> {
+        self.documents.values()
+    }
+
+    /// The open document for `uri`, or else the file it points to read from disk
+    pub(crate) fn get_or_read(&self, uri: &DocumentUri) -> Option<Document> {
+        if let Some(document) = self.get(uri) {
+            return Some(document.clone());
+        }
+        let path = uri.to_file_path()?;
+        fs::read_to_string(path)
+            .ok()
+            .map(|text| Document::new(uri.clone(), text, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(uri: &str) -> DocumentUri {
+        DocumentUri::parse(uri).unwrap()
+    }
+
+    fn change(range: Option<Range>, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range,
+            range_length: None,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn changes_apply_in_order_at_positions_of_the_encoding() {
+        let mut store = DocumentStore::default();
+        let page = uri("file:///app/page.html");
+        store.open(
+            page.clone(),
+            "<p>日本 {{ vm.a }}</p>\r\n<p></p>".to_owned(),
+            1,
+        );
+        let range = |start, end| Some(Range::new(Position::new(0, start), Position::new(0, end)));
+        store.change(
+            &page,
+            2,
+            vec![
+                // `a`, after two characters of 3 bytes each
+                change(range(12, 13), "name"),
+                change(
+                    Some(Range::new(Position::new(1, 3), Position::new(1, 3))),
+                    "!",
+                ),
+            ],
+            PositionEncoding::Utf16,
+        );
+        let document = store.get(&page).unwrap();
+        assert_eq!(document.text(), "<p>日本 {{ vm.name }}</p>\r\n<p>!</p>");
+        assert_eq!(document.version(), Some(2));
+        assert_eq!(
+            document.range(20, 22, PositionEncoding::Utf16),
+            Range::new(Position::new(0, 16), Position::new(0, 18))
+        );
+    }
+
+    #[test]
+    fn changes_without_a_range_replace_the_text() {
+        let mut document =
+            Document::new(uri("untitled:Untitled-1"), "one\ntwo".to_owned(), Some(1));
+        document.apply_change(change(None, "three"), PositionEncoding::Utf16);
+        assert_eq!(document.text(), "three");
+        assert_eq!(
+            document.offset(Position::new(1, 0), PositionEncoding::Utf16),
+            5
+        );
+    }
+
+    #[test]
+    fn invalid_uris_are_errors() {
+        let document = Document::new(uri("file:///app/page.html?a b"), String::new(), None);
+        assert!(document.uri().is_err());
+        let document = Document::new(uri("file:///app/my page.html"), String::new(), None);
+        assert_eq!(
+            document.uri().unwrap().as_str(),
+            "file:///app/my%20page.html"
+        );
+    }
+}
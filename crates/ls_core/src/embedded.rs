@@ -0,0 +1,202 @@
+//! Templates written inline in scripts, the `template` of a component,
+//! directive or route: `template: '<p>{{$ctrl.name}}</p>'`
+
+use std::ops::Range;
+
+use tree_sitter::Node;
+
+use crate::{
+    Workspace,
+    controller::{Symbol, TemplateBinding, named_controller},
+    document::Document,
+    registry::{function_name, parse_script, property, string_value, visit},
+    routes::keys,
+};
+
+/// What a component's template calls its controller unless `controllerAs`
+/// says otherwise
+const COMPONENT_ALIAS: &str = "$ctrl";
+
+/// A template embedded in a script
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddedTemplate {
+    /// The script with all but the template blanked out. It parses as the
+    /// template, with the offsets and positions it has in the script
+    pub(crate) document: Document,
+    /// Range of the template in the script, inside the quotes
+    pub(crate) range: Range<usize>,
+    /// The `controller` of the definition the template is in
+    pub(crate) controller: Option<EmbeddedController>,
+    /// What the template calls the controller, from `controllerAs` or from
+    /// `controller: 'OrderController as order'`
+    pub(crate) controller_as: Option<String>,
+    /// The `bindings` of the component, which its controller is given
+    pub(crate) bindings: Vec<Symbol>,
+}
+
+/// The controller of a definition with an inline template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EmbeddedController {
+    /// Registered with or declared as this name
+    Named(String),
+    /// A class or function written right there, in the script
+    Inline,
+}
+
+/// The templates embedded in the script `document`, in the order they're
+/// written
+pub(crate) fn embedded_templates(document: &Document) -> Vec<EmbeddedTemplate> {
+    let Some((_, tree)) = parse_script(document) else {
+        return vec![];
+    };
+    let text = document.text();
+    let mut templates = vec![];
+    visit(tree.root_node(), &mut |node| {
+        let Some(template) = property(node, "template", text)
+            .filter(|template| matches!(template.kind(), "string" | "template_string"))
+        else {
+            return;
+        };
+        templates.push(embedded_template(document, node, template));
+    });
+    templates
+}
+
+/// The template embedded in the script `document` that `offset` is in
+pub(crate) fn embedded_template_at(document: &Document, offset: usize) -> Option<EmbeddedTemplate> {
+    embedded_templates(document)
+        .into_iter()
+        .find(|template| template.range.start <= offset && offset <= template.range.end)
+}
+
+/// The template at `offset` of `document`: a template is its own, and a
+/// script has the one embedded there if any
+pub(crate) fn template_at(
+    document: &Document,
+    offset: usize,
+    workspace: &Workspace,
+) -> Option<Document> {
+    if workspace.is_script(&document.uri) {
+        embedded_template_at(document, offset).map(|template| template.document)
+    } else {
+        Some(document.clone())
+    }
+}
+
+/// What the template embedded in a script, `template`, is bound to: the
+/// `controller` of its definition, called `$ctrl` in it unless the
+/// definition says otherwise. The bindings of a component are members of
+/// its controller too
+pub(crate) fn embedded_binding(template: &Document, workspace: &Workspace) -> TemplateBinding {
+    let Some((script, embedded)) = find_embedded(template, workspace) else {
+        return TemplateBinding::default();
+    };
+    let mut aliases = workspace.config.controller_aliases.clone();
+    let alias = embedded
+        .controller_as
+        .unwrap_or_else(|| COMPONENT_ALIAS.to_owned());
+    if !aliases.contains(&alias) {
+        aliases.push(alias);
+    }
+    let controller = match &embedded.controller {
+        Some(EmbeddedController::Named(name)) => named_controller(name, &script.uri, workspace),
+        Some(EmbeddedController::Inline) => Some(script.clone()),
+        None => None,
+    };
+    TemplateBinding {
+        controller,
+        aliases,
+        given: embedded
+            .bindings
+            .into_iter()
+            .map(|binding| (script.clone(), binding))
+            .collect(),
+    }
+}
+
+/// The script `template` is embedded in, and what's known of it there
+pub(crate) fn find_embedded(
+    template: &Document,
+    workspace: &Workspace,
+) -> Option<(Document, EmbeddedTemplate)> {
+    let script = workspace.documents.get_or_read(&template.uri)?;
+    let embedded = embedded_templates(&script)
+        .into_iter()
+        .find(|embedded| embedded.document.text() == template.text())?;
+    Some((script, embedded))
+}
+
+/// The template `template`, the value of the `template` of the object
+/// `definition` in `document`
+fn embedded_template(document: &Document, definition: Node, template: Node) -> EmbeddedTemplate {
+    let text = document.text();
+    let range = template.start_byte() + 1..template.end_byte().saturating_sub(1);
+    // `${}`s are left out, and so are the `\` of escapes
+    let mut holes = vec![];
+    let mut cursor = template.walk();
+    for child in template.named_children(&mut cursor) {
+        match child.kind() {
+            "template_substitution" => holes.push(child.byte_range()),
+            "escape_sequence" => holes.push(child.start_byte()..child.start_byte() + 1),
+            _ => {}
+        }
+    }
+    let mut controller = None;
+    let mut controller_as = property(definition, "controllerAs", text)
+        .and_then(|alias| string_value(alias, text))
+        .map(str::to_owned);
+    if let Some(value) = property(definition, "controller", text) {
+        match string_value(value, text) {
+            Some(name) => {
+                let mut words = name.split_whitespace();
+                controller = words
+                    .next()
+                    .map(|name| EmbeddedController::Named(name.to_owned()));
+                if let (Some("as"), Some(alias)) = (words.next(), words.next()) {
+                    controller_as = Some(alias.to_owned());
+                }
+            }
+            None => {
+                controller = function_name(value, text)
+                    .map(EmbeddedController::Named)
+                    .or_else(|| {
+                        matches!(
+                            value.kind(),
+                            "class" | "function_expression" | "function" | "arrow_function"
+                        )
+                        .then_some(EmbeddedController::Inline)
+                    })
+            }
+        }
+    }
+    let bindings = property(definition, "bindings", text)
+        .map(|bindings| keys(bindings, text))
+        .unwrap_or_default();
+    EmbeddedTemplate {
+        document: Document::new(
+            document.uri.clone(),
+            blank_outside(text, &range, &holes),
+            document.version,
+        ),
+        range,
+        controller,
+        controller_as,
+        bindings,
+    }
+}
+
+/// `text` with spaces for what's outside `range` or inside one of `holes`.
+/// Line breaks are kept, and so are characters other than ASCII, so that
+/// what's left is at the same line and column whatever they're counted in
+fn blank_outside(text: &str, range: &Range<usize>, holes: &[Range<usize>]) -> String {
+    text.char_indices()
+        .map(|(at, c)| {
+            let is_kept = range.contains(&at) && !holes.iter().any(|hole| hole.contains(&at));
+            if is_kept || matches!(c, '\n' | '\r') || !c.is_ascii() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,161 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::jsonrpc::JsonRpcRequestId;
+
+pub(crate) type LSResult<T> = Result<T, LSError>;
+
+/// Errors that can be reported back to the client.
+///
+/// Covers the JSON-RPC 2.0 error codes as well as the ones LSP reserves
+/// in the range -32899..=-32800 and -32099..=-32000
+#[derive(Error, Debug)]
+pub(crate) enum LSError {
+    #[error("Internal error: {message}")]
+    InternalError { message: String },
+    #[error("Invalid request: {message}")]
+    InvalidRequest { message: String },
+    #[error("Invalid params: {message}")]
+    InvalidParams { message: String },
+    #[error("Method not found: '{0}'")]
+    MethodNotFound(String),
+    #[error("Parsing error: '{0}'")]
+    ParseError(ParseError),
+    /// A request other than `initialize` came before it
+    #[error("Server not initialized")]
+    ServerNotInitialized,
+    /// Kept for clients of older protocol versions, nothing raises it
+    #[allow(dead_code)]
+    #[error("Unknown error: {message}")]
+    UnknownErrorCode { message: String },
+    /// A request failed even though it was syntactically correct
+    /// and the server understood it
+    #[error("Request failed: {message}")]
+    RequestFailed {
+        message: String,
+        data: Option<serde_json::Value>,
+    },
+    /// The server cancelled the request. Only for requests that are marked
+    /// as server cancellable by the client, which none of ours are yet
+    #[allow(dead_code)]
+    #[error("Server cancelled the request")]
+    ServerCancelled,
+    /// The document changed while the request was waiting to be handled
+    #[error("Content modified")]
+    ContentModified,
+    /// The client cancelled the request
+    #[error("Request cancelled")]
+    RequestCancelled,
+}
+
+impl LSError {
+    pub(crate) fn code(&self) -> i32 {
+        match self {
+            LSError::ParseError(_) => -32700,
+            LSError::InvalidRequest { message: _ } => -32600,
+            LSError::MethodNotFound(_) => -32601,
+            LSError::InvalidParams { message: _ } => -32602,
+            LSError::InternalError { message: _ } => -32603,
+            LSError::ServerNotInitialized => -32002,
+            LSError::UnknownErrorCode { message: _ } => -32001,
+            LSError::RequestFailed { .. } => -32803,
+            LSError::ServerCancelled => -32802,
+            LSError::ContentModified => -32801,
+            LSError::RequestCancelled => -32800,
+        }
+    }
+    pub(crate) fn message(&self) -> String {
+        self.to_string()
+    }
+
+    /// Additional information about the error, sent as the `data` field
+    pub(crate) fn data(&self) -> Option<serde_json::Value> {
+        match self {
+            LSError::ParseError(ParseError::JsonParsing((e, _))) => Some(serde_json::json!({
+                "line": e.line(),
+                "column": e.column(),
+            })),
+            LSError::MethodNotFound(method) => Some(serde_json::json!({ "method": method })),
+            LSError::RequestFailed { message: _, data } => data.clone(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn internal<E: std::error::Error>(e: E) -> Self {
+        Self::InternalError {
+            message: format!("{e}"),
+        }
+    }
+
+    pub(crate) fn invalid_params<E: std::error::Error>(e: E) -> Self {
+        Self::InvalidParams {
+            message: format!("{e}"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum ParseError {
+    #[error("Header invalid")]
+    Header,
+    /// The client closed the input stream
+    #[error("End of input")]
+    Eof,
+    #[error("IO error while parsing")]
+    Io(#[from] io::Error),
+    #[error("JSON parsing error. e: {}", .0.0)]
+    JsonParsing((serde_json::Error, String)),
+    /// Valid JSON that isn't a valid JSON-RPC message.
+    /// `id` is kept when it could be recovered so the error can be replied to
+    #[error("Invalid message: {error}")]
+    InvalidMessage {
+        id: Option<JsonRpcRequestId>,
+        error: serde_json::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lsp_reserved_codes() {
+        let codes = [
+            (LSError::ServerNotInitialized, -32002),
+            (
+                LSError::UnknownErrorCode {
+                    message: String::new(),
+                },
+                -32001,
+            ),
+            (
+                LSError::RequestFailed {
+                    message: String::new(),
+                    data: None,
+                },
+                -32803,
+            ),
+            (LSError::ServerCancelled, -32802),
+            (LSError::ContentModified, -32801),
+            (LSError::RequestCancelled, -32800),
+        ];
+        for (err, code) in codes {
+            assert_eq!(err.code(), code, "{err}");
+            assert_eq!(err.data(), None);
+        }
+    }
+
+    #[test]
+    fn failed_requests_carry_their_data() {
+        let err = LSError::RequestFailed {
+            message: "Couldn't read 'x.html'".to_owned(),
+            data: Some(serde_json::json!({ "uri": "file:///x.html" })),
+        };
+        assert_eq!(err.message(), "Request failed: Couldn't read 'x.html'");
+        assert_eq!(
+            err.data(),
+            Some(serde_json::json!({ "uri": "file:///x.html" }))
+        );
+    }
+}
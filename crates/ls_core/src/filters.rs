@@ -0,0 +1,114 @@
+//! Filters, the `| name:argument` at the end of template expressions
+
+use std::ops::Range;
+
+/// A filter that ships with AngularJS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NgFilter {
+    pub(crate) name: &'static str,
+    /// The input first, then the arguments given after `:`
+    pub(crate) parameters: &'static [&'static str],
+    /// What it does, in a sentence
+    pub(crate) summary: &'static str,
+}
+
+/// Filters that ship with AngularJS
+pub(crate) const NG_FILTERS: &[NgFilter] = &[
+    NgFilter {
+        name: "currency",
+        parameters: &["amount", "symbol", "fractionSize"],
+        summary: "Formats a number as a currency, with the locale's symbol by default.",
+    },
+    NgFilter {
+        name: "date",
+        parameters: &["date", "format", "timezone"],
+        summary: "Formats a date, a timestamp or an ISO 8601 string.",
+    },
+    NgFilter {
+        name: "filter",
+        parameters: &["array", "expression", "comparator", "anyPropertyKey"],
+        summary: "Selects the items of an array matching a string, an object or a predicate.",
+    },
+    NgFilter {
+        name: "json",
+        parameters: &["object", "spacing"],
+        summary: "Converts a value to JSON.",
+    },
+    NgFilter {
+        name: "limitTo",
+        parameters: &["input", "limit", "begin"],
+        summary: "Keeps the first, or last when negative, `limit` items or characters.",
+    },
+    NgFilter {
+        name: "lowercase",
+        parameters: &["input"],
+        summary: "Converts a string to lowercase.",
+    },
+    NgFilter {
+        name: "number",
+        parameters: &["number", "fractionSize"],
+        summary: "Formats a number with grouped thousands and `fractionSize` decimals.",
+    },
+    NgFilter {
+        name: "orderBy",
+        parameters: &["collection", "expression", "reverse", "comparator"],
+        summary: "Sorts an array by an expression, a property name or several of them.",
+    },
+    NgFilter {
+        name: "uppercase",
+        parameters: &["input"],
+        summary: "Converts a string to uppercase.",
+    },
+];
+
+/// The built-in filter `name`
+pub(crate) fn ng_filter(name: &str) -> Option<&'static NgFilter> {
+    NG_FILTERS.iter().find(|filter| filter.name == name)
+}
+
+/// How the filter `name` with `parameters` is used in a template,
+/// `amount | currency:symbol:fractionSize`
+pub(crate) fn usage<S: AsRef<str>>(name: &str, parameters: &[S]) -> String {
+    let mut usage = match parameters.first() {
+        Some(input) => format!("{} | {name}", input.as_ref()),
+        None => format!("input | {name}"),
+    };
+    for parameter in parameters.iter().skip(1) {
+        usage.push(':');
+        usage.push_str(parameter.as_ref());
+    }
+    usage
+}
+
+/// Ranges of the filter names in `range`, an expression of `text`. The name
+/// is empty right after a `|` it isn't typed after yet
+pub(crate) fn filter_names(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let expression = &text[range.clone()];
+    let mut names = vec![];
+    let mut quote = None;
+    let mut chars = expression.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '|') => {
+                // `||` is a logical or
+                if chars.next_if(|&(_, next)| next == '|').is_some() {
+                    continue;
+                }
+                let rest = &expression[at + 1..];
+                let start = at + 1 + (rest.len() - rest.trim_start().len());
+                let end = expression[start..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .map_or(expression.len(), |len| start + len);
+                names.push(range.start + start..range.start + end);
+            }
+            _ => {}
+        }
+    }
+    names
+}
@@ -0,0 +1,255 @@
+//! What the scripts of a workspace declare, kept until they change so that
+//! requests don't parse every script of the workspace again, and listed for
+//! a whole project from the command line
+
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use lsp_types::Position;
+
+use crate::{
+    Workspace,
+    config::Config,
+    controller::Symbol,
+    document::Document,
+    line_index::PositionEncoding,
+    links::{TemplateUrl, template_urls},
+    registry::{Registration, RegistrationKind, classes, registrations},
+    routes::{Route, routes},
+    uri::DocumentUri,
+    workspace::find_scripts,
+};
+
+/// What one script declares: its registrations, then its routes, then its
+/// `templateUrl`s
+#[derive(Debug, Clone)]
+pub struct ScriptEntries {
+    /// Path of the script, under the root that was indexed
+    pub path: PathBuf,
+    pub entries: Vec<IndexEntry>,
+}
+
+/// A registration, route or `templateUrl` of a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub kind: EntryKind,
+    /// Name of a registration, or the `templateUrl` of a route or on its own
+    pub name: String,
+    /// Where `name` is, with `character` counted in unicode code points.
+    /// `None` for routes
+    pub position: Option<Position>,
+    /// Controller of a component or a route when it's given by name, as in
+    /// `OrderController` or `OrderController as order`
+    pub controller: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Component,
+    Directive,
+    Filter,
+    Controller,
+    Route,
+    TemplateUrl,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EntryKind::Component => "component",
+            EntryKind::Directive => "directive",
+            EntryKind::Filter => "filter",
+            EntryKind::Controller => "controller",
+            EntryKind::Route => "route",
+            EntryKind::TemplateUrl => "templateUrl",
+        })
+    }
+}
+
+/// Lists what each script of the project at `root` declares, or `root`
+/// itself when it's a file. Hidden directories, `node_modules` and the
+/// scripts `config` excludes are skipped, and so are scripts that declare
+/// nothing
+pub fn index_project(root: &Path, config: Config) -> io::Result<Vec<ScriptEntries>> {
+    let mut files = vec![];
+    let mut workspace = if root.is_dir() {
+        find_scripts(root, &mut files)?;
+        Workspace::with_root(std::path::absolute(root)?)
+    } else {
+        files.push(root.to_owned());
+        Workspace::new()
+    };
+    workspace.set_config(config).map_err(io::Error::other)?;
+    files.sort();
+
+    let mut scripts = vec![];
+    for path in files {
+        let uri = DocumentUri::from_file_path(&std::path::absolute(&path)?);
+        if root.is_dir() && !workspace.is_project_script(&uri) {
+            continue;
+        }
+        let script = Document::new(uri, fs::read_to_string(&path)?, None);
+        let entries = entries(&script, &workspace.index(&script));
+        if !entries.is_empty() {
+            scripts.push(ScriptEntries { path, entries });
+        }
+    }
+    Ok(scripts)
+}
+
+fn entries(script: &Document, index: &ScriptIndex) -> Vec<IndexEntry> {
+    let position = |range: &Range<usize>| {
+        Some(
+            script
+                .range(range.start, range.end, PositionEncoding::Utf32)
+                .start,
+        )
+    };
+    let registrations = index.registrations.iter().map(|registration| IndexEntry {
+        kind: match registration.kind {
+            RegistrationKind::Component => EntryKind::Component,
+            RegistrationKind::Directive => EntryKind::Directive,
+            RegistrationKind::Filter => EntryKind::Filter,
+            RegistrationKind::Controller => EntryKind::Controller,
+        },
+        name: registration.name.clone(),
+        position: position(&registration.range),
+        controller: registration.controller.clone(),
+    });
+    let routes = index.routes.iter().map(|route| IndexEntry {
+        kind: EntryKind::Route,
+        name: route.template_url.clone(),
+        position: None,
+        controller: match (&route.controller, &route.controller_as) {
+            (Some(controller), Some(alias)) => Some(format!("{controller} as {alias}")),
+            (controller, _) => controller.clone(),
+        },
+    });
+    let template_urls = index.template_urls.iter().map(|url| IndexEntry {
+        kind: EntryKind::TemplateUrl,
+        name: url.url.clone(),
+        position: position(&url.range),
+        controller: None,
+    });
+    registrations.chain(routes).chain(template_urls).collect()
+}
+
+/// The registrations, routes, `templateUrl`s and classes of a script
+#[derive(Debug, Default)]
+pub(crate) struct ScriptIndex {
+    pub(crate) registrations: Vec<Registration>,
+    pub(crate) routes: Vec<Route>,
+    pub(crate) template_urls: Vec<TemplateUrl>,
+    /// Classes declared in the script, with the range of their name
+    pub(crate) classes: Vec<Symbol>,
+}
+
+impl ScriptIndex {
+    fn new(script: &Document) -> Self {
+        Self {
+            registrations: registrations(script),
+            routes: routes(script),
+            template_urls: template_urls(script),
+            classes: classes(script),
+        }
+    }
+
+    /// Range of the name of the class `name`, if the script declares it
+    pub(crate) fn find_class(&self, name: &str) -> Option<Range<usize>> {
+        self.classes
+            .iter()
+            .find(|class| class.name == name)
+            .map(|class| class.range.clone())
+    }
+}
+
+/// Scripts listed and read from disk, and their indexes.
+///
+/// Indexes are kept for the version of the script they were made from, so
+/// that an open script that changes is indexed again. What's read from disk
+/// is kept until [`Self::forget`] or [`Self::forget_listings`] say otherwise
+#[derive(Debug, Default)]
+pub(crate) struct ScriptCache {
+    cached: Mutex<Cached>,
+}
+
+#[derive(Debug, Default)]
+struct Cached {
+    /// The scripts under each directory looked into
+    listings: HashMap<PathBuf, Arc<Vec<DocumentUri>>>,
+    /// Scripts read from disk
+    files: HashMap<DocumentUri, Document>,
+    indexes: HashMap<DocumentUri, (Option<i32>, Arc<ScriptIndex>)>,
+}
+
+impl ScriptCache {
+    /// The scripts under `dir`, listed with `list` unless they already are
+    pub(crate) fn listing(
+        &self,
+        dir: &Path,
+        list: impl FnOnce() -> Vec<DocumentUri>,
+    ) -> Arc<Vec<DocumentUri>> {
+        if let Some(listing) = self.lock().listings.get(dir) {
+            return listing.clone();
+        }
+        let listing = Arc::new(list());
+        self.lock().listings.insert(dir.to_owned(), listing.clone());
+        listing
+    }
+
+    /// The script at `uri` on disk, read with `read` unless it already is
+    pub(crate) fn file(
+        &self,
+        uri: &DocumentUri,
+        read: impl FnOnce() -> Option<Document>,
+    ) -> Option<Document> {
+        if let Some(file) = self.lock().files.get(uri) {
+            return Some(file.clone());
+        }
+        let file = read()?;
+        self.lock().files.insert(uri.clone(), file.clone());
+        Some(file)
+    }
+
+    /// The index of `script`, made again if the script changed since
+    pub(crate) fn index(&self, script: &Document) -> Arc<ScriptIndex> {
+        if let Some((version, index)) = self.lock().indexes.get(&script.uri)
+            && *version == script.version
+        {
+            return index.clone();
+        }
+        let index = Arc::new(ScriptIndex::new(script));
+        self.lock()
+            .indexes
+            .insert(script.uri.clone(), (script.version, index.clone()));
+        index
+    }
+
+    /// Drops what's known of the script at `uri`, which changed
+    pub(crate) fn forget(&self, uri: &DocumentUri) {
+        let mut cached = self.lock();
+        cached.files.remove(uri);
+        cached.indexes.remove(uri);
+    }
+
+    /// Lists directories again next time, scripts were added or removed
+    pub(crate) fn forget_listings(&self) {
+        self.lock().listings.clear();
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.lock() = Cached::default();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Cached> {
+        // what's cached stays consistent even if a thread panicked holding it
+        self.cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
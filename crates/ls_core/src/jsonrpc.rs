@@ -0,0 +1,177 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LSError;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum LSMessage {
+    Request(LSMessageRequest),
+    Notification(LSMessageNotification),
+    Response(JsonRpcGenericResponse),
+}
+
+pub(crate) type LSMessageRequest = JsonRpcRequest<JsonRpcGenericRequestBody>;
+pub(crate) type LSMessageNotification = JsonRpcNotification<JsonRpcGenericRequestBody>;
+
+impl LSMessageRequest {
+    pub(crate) fn new(id: JsonRpcRequestId, method: &str, params: serde_json::Value) -> Self {
+        Self {
+            id,
+            request: JsonRpcGenericRequestBody {
+                method: method.to_owned(),
+                params: Some(params),
+            },
+            base: JsonRpcMessageBase {
+                jsonrpc: "2.0".to_owned(),
+            },
+        }
+    }
+}
+
+impl LSMessageNotification {
+    pub(crate) fn new(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            notification: JsonRpcGenericRequestBody {
+                method: method.to_owned(),
+                params: Some(params),
+            },
+            base: JsonRpcMessageBase {
+                jsonrpc: "2.0".to_owned(),
+            },
+        }
+    }
+}
+
+pub(crate) type LSMessageResponse = JsonRpcResponse<serde_json::Value>;
+
+impl LSMessageResponse {
+    pub(crate) fn new(id: JsonRpcRequestId, body: serde_json::Value) -> Self {
+        Self {
+            id,
+            result: body,
+            base: JsonRpcMessageBase {
+                jsonrpc: "2.0".to_owned(),
+            },
+        }
+    }
+}
+
+pub(crate) type LSMessageError = JsonRpcError<LSMessageErrorBody>;
+
+impl LSMessageError {
+    /// `id` is `None` only when the request's id couldn't be determined,
+    /// i.e. for parse errors and invalid requests
+    pub(crate) fn new(id: Option<JsonRpcRequestId>, body: LSMessageErrorBody) -> Self {
+        Self {
+            id,
+            error: body,
+            base: JsonRpcMessageBase {
+                jsonrpc: "2.0".to_owned(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct LSMessageErrorBody {
+    code: i32,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<serde_json::Value>,
+}
+
+impl LSMessageErrorBody {
+    pub(crate) fn from(kind: LSError) -> Self {
+        LSMessageErrorBody {
+            code: kind.code(),
+            message: kind.message(),
+            data: kind.data(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcMessageBase {
+    /// 2.0
+    jsonrpc: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcError<ErrorBody> {
+    /// `null` when the id couldn't be detected, e.g. on a parse error
+    id: Option<JsonRpcRequestId>,
+    error: ErrorBody,
+    #[serde(flatten)]
+    base: JsonRpcMessageBase,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcResponse<ResponseBody> {
+    id: JsonRpcRequestId,
+    result: ResponseBody,
+    #[serde(flatten)]
+    base: JsonRpcMessageBase,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcNotification<NotificationBody> {
+    /// How do i enforce that NotificationBody must have serde deserializer implemented
+    /// such that it's a JSON object containing keys method: string and params: any
+    #[serde(flatten)]
+    pub(crate) notification: NotificationBody,
+    #[serde(flatten)]
+    base: JsonRpcMessageBase,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcRequest<RequestBody> {
+    pub(crate) id: JsonRpcRequestId,
+    /// How do i enforce that ReqeustBody must have serde deserializer implemented
+    /// such that it's a JSON object containing keys method: string and params: any
+    #[serde(flatten)]
+    pub(crate) request: RequestBody,
+    #[serde(flatten)]
+    base: JsonRpcMessageBase,
+}
+
+/// `method` and `params` of a request or a notification.
+///
+/// `params` are left untyped here and deserialized into the
+/// [`lsp_types`] type for the method once the method is known
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct JsonRpcGenericRequestBody {
+    pub(crate) method: String,
+    pub(crate) params: Option<serde_json::Value>,
+}
+
+/// A response sent by the client to a request made by the server
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum JsonRpcGenericResponse {
+    Result {
+        id: JsonRpcRequestId,
+        result: serde_json::Value,
+    },
+    Error {
+        id: Option<JsonRpcRequestId>,
+        error: LSMessageErrorBody,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub(crate) enum JsonRpcRequestId {
+    String(String),
+    Integer(i64),
+}
+
+impl fmt::Display for JsonRpcRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonRpcRequestId::String(id) => write!(f, "{id}"),
+            JsonRpcRequestId::Integer(id) => write!(f, "{id}"),
+        }
+    }
+}
@@ -1,259 +1,266 @@
+//! Language server for AngularJS templates.
+//!
+//! [`LServer`] serves an editor over JSON-RPC. The analysis it's built on is
+//! available on its own through [`Workspace`], for tools that don't need a client
+
 use std::{
-    fs,
-    io::{self, Read},
+    any::Any,
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    time::Instant,
 };
 
-use serde::{Deserialize, Serialize};
-// use streaming_iterator::StreamingIterator;
-use thiserror::Error;
-use tracing::{debug, instrument};
-use tree_sitter::{Query, QueryCursor, StreamingIterator};
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum LSMessage {
-    Request(LSMessageRequest),
-    Notification(LSMessageNotification),
-    Response,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct DefinitionClientCapabilities {
-    link_support: Option<bool>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct TextDocumentClientCapabilities {
-    definition: DefinitionClientCapabilities,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct LSClientCapabilities {
-    workspace: serde_json::Value,
-    text_document: Option<TextDocumentClientCapabilities>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "method", content = "params")]
-#[serde(rename_all = "lowercase")]
-enum LSMessageNotificationBody {
-    Initialized {},
-    Exit,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LsTypePosition {
-    character: u32,
-    line: u32,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LsTypeTextDocument {
-    uri: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "method", content = "params")]
-#[serde(rename_all = "lowercase")]
-enum LSMessageRequestBody {
-    Initialize {
-        capabilities: LSClientCapabilities,
-    },
-    Shutdown,
-    #[serde(rename = "textDocument/definition")]
-    #[serde(rename_all = "camelCase")]
-    TextDocumentDefinition {
-        position: LsTypePosition,
-        text_document: LsTypeTextDocument,
-    },
-    #[serde(untagged)]
-    Unknown {
-        method: String,
-        params: Option<serde_json::Value>,
-    },
-}
+use config::{CONFIG_FILE_NAME, CONFIG_SECTION, ConfigError};
+use error::{LSError, LSResult, ParseError};
+use jsonrpc::{
+    JsonRpcGenericResponse, JsonRpcRequestId, LSMessage, LSMessageError, LSMessageErrorBody,
+    LSMessageResponse,
+};
+use line_index::PositionEncoding;
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, ConfigurationItem,
+    ConfigurationParams, DidChangeConfigurationParams, DidChangeWatchedFilesParams,
+    DidChangeWorkspaceFoldersParams, DocumentLink, DocumentLinkOptions, DocumentLinkParams,
+    FileChangeType, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, Location,
+    MarkupContent, MarkupKind, MessageType, OneOf, PublishDiagnosticsParams, ReferenceParams,
+    ServerCapabilities, ServerInfo, ShowMessageParams, TextDocumentIdentifier,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Uri, WorkspaceFolder,
+    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
+    notification::{self, Notification},
+    request::{self, Request},
+};
+use project::{Project, Projects};
+use record::{Direction, Recorder};
+use serde::{Serialize, de::DeserializeOwned};
+use template::Template;
+use tracing::{debug, error, instrument};
+use uri::DocumentUri;
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum LsType {
-    Null,
-}
+pub mod check;
+mod client;
+mod completion;
+pub mod config;
+mod controller;
+mod diagnostics;
+mod directives;
+mod document;
+mod embedded;
+mod error;
+mod filters;
+pub mod index;
+mod jsonrpc;
+mod line_index;
+mod links;
+pub mod navigation;
+mod project;
+pub mod protocol;
+pub mod record;
+mod registry;
+pub mod replay;
+mod routes;
+mod template;
+pub mod testing;
+mod tsconfig;
+mod uri;
+mod workspace;
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum LSMessageResponseBody {
-    Initialize(LSMessageResponseInitialize),
-    Location(LSMessageResponseLocation),
-    RawType(LsType),
-    Shutdown,
-}
+pub use client::Client;
+pub use config::Config;
+pub use document::Document;
+pub use workspace::{InvalidUri, Member, TemplateDiagnostics, Workspace};
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LSInfo {
-    name: String,
-    version: String,
-}
+/// The user is told about crashing handlers every time this many have crashed
+const PANIC_REPORT_THRESHOLD: u32 = 3;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LsTypeRange {
-    start: LsTypePosition,
-    end: LsTypePosition,
+pub struct LServer {
+    /// Where messages from the client are read from
+    input: io::BufReader<Box<dyn Read + Send>>,
+    /// Messages read ahead of the one being handled, see [`Self::skipped`]
+    queued: VecDeque<Result<LSMessage, ParseError>>,
+    client: Client,
+    /// A project for each workspace folder, with its documents and config
+    projects: Projects,
+    /// Where the config comes from besides the defaults
+    settings: Settings,
+    /// Requests sent to the client that haven't been answered yet, by id
+    pending_requests: HashMap<JsonRpcRequestId, PendingRequest>,
+    /// Number of handlers that panicked so far
+    panic_count: u32,
+    /// Whether `initialize` was answered, before which requests are refused
+    initialized: bool,
+    /// Whether `shutdown` was answered, after which requests are refused
+    shut_down: bool,
 }
 
-impl LsTypeRange {
-    fn range(start: (usize, usize), end: (usize, usize)) -> Self {
-        Self {
-            start: LsTypePosition {
-                line: start.0 as u32,
-                character: start.1 as u32,
-            },
-            end: LsTypePosition {
-                line: end.0 as u32,
-                character: end.1 as u32,
-            },
-        }
-    }
-    // fn beginning() -> Self {
-    //     Self {
-    //         start: LsTypePosition {
-    //             character: 0,
-    //             line: 0,
-    //         },
-    //         end: LsTypePosition {
-    //             character: 0,
-    //             line: 0,
-    //         },
-    //     }
-    // }
+/// The layers [`Config`] is merged from, lowest priority first
+#[derive(Default)]
+struct Settings {
+    /// `initializationOptions` of `initialize`
+    initialization_options: serde_json::Value,
+    /// Whether the client answers `workspace/configuration`. When it doesn't,
+    /// its settings are taken from `workspace/didChangeConfiguration`
+    pull_client: bool,
+    /// Project config file given on the command line, instead of the
+    /// [`CONFIG_FILE_NAME`] at the root of each workspace folder
+    file: Option<PathBuf>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LSMessageResponseLocation {
-    uri: String,
-    range: LsTypeRange,
+/// What a request sent to the client was for
+#[derive(Debug)]
+enum PendingRequest {
+    /// `workspace/configuration` for the folders with these URIs, in order.
+    /// `None` asks for the settings outside every folder
+    Configuration(Vec<Option<Uri>>),
 }
 
-impl LSMessageResponseLocation {
-    fn new(uri: String, range: LsTypeRange) -> Self {
-        Self { uri, range }
+impl Default for LServer {
+    fn default() -> Self {
+        Self::with_io(io::stdin(), io::stdout())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct LSMessageResponseInitialize {
-    capabilities: serde_json::Value,
-    server_info: LSInfo,
-}
-
-impl LSMessageResponseInitialize {
-    fn new(name: &str, version: &str, _capabilities: LSClientCapabilities) -> Self {
-        let server_capabilities = serde_json::json!({
-            "definitionProvider": true
-        });
-        Self {
-            capabilities: server_capabilities,
-            server_info: LSInfo {
-                name: name.to_owned(),
-                version: version.to_owned(),
-            },
-        }
+impl LServer {
+    /// A server talking to the client over stdin and stdout
+    pub fn new() -> Self {
+        Self::default()
     }
-}
-
-type LSMessageRequest = JsonRpcRequest<LSMessageRequestBody>;
-type LSMessageNotification = JsonRpcNotification<LSMessageNotificationBody>;
 
-type LSMessageResponse = JsonRpcResponse<LSMessageResponseBody>;
-
-impl LSMessageResponse {
-    fn new(id: JsonRpcRequestId, body: LSMessageResponseBody) -> Self {
+    /// A server reading messages from `input` and writing to `output`,
+    /// e.g. the two halves of a socket
+    pub fn with_io(input: impl Read + Send + 'static, output: impl Write + Send + 'static) -> Self {
         Self {
-            id,
-            result: body,
-            base: JsonRpcMessageBase {
-                jsonrpc: "2.0".to_owned(),
-            },
+            input: io::BufReader::new(Box::new(input)),
+            queued: VecDeque::new(),
+            client: Client::new(Box::new(output)),
+            projects: Projects::default(),
+            settings: Settings::default(),
+            pending_requests: HashMap::new(),
+            panic_count: 0,
+            initialized: false,
+            shut_down: false,
         }
     }
-}
-
-type LSMessageError = JsonRpcError<LSMessageErrorBody>;
 
-impl LSMessageError {
-    fn new(id: JsonRpcRequestId, body: LSMessageErrorBody) -> Self {
-        Self {
-            id,
-            error: body,
-            base: JsonRpcMessageBase {
-                jsonrpc: "2.0".to_owned(),
-            },
-        }
+    /// Reads the project config from `path` rather than from the
+    /// [`CONFIG_FILE_NAME`] at the root of each workspace folder
+    pub fn set_config_file(&mut self, path: impl Into<PathBuf>) {
+        self.settings.file = Some(path.into());
     }
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LSMessageErrorBody {
-    code: i32,
-    message: String,
-    // data: ErrorData,
-}
-
-impl LSMessageErrorBody {
-    fn from(kind: LSError) -> Self {
-        LSMessageErrorBody {
-            code: kind.code(),
-            message: kind.message(),
-        }
+    /// Records every message read and sent from now on to `output`,
+    /// as a line of JSON each. See [`replay`] for playing them back
+    pub fn record_to(&mut self, output: impl Write + Send + 'static) {
+        self.client.set_recorder(Recorder::new(Box::new(output)));
     }
-}
 
-pub struct LServer {}
-impl LServer {
-    pub fn new() -> Self {
-        Self {}
+    /// Handle for sending messages to the client this server talks to
+    pub fn client(&self) -> Client {
+        self.client.clone()
     }
 
     /// Blocks the thread and processes each message
     /// till the server exits
-    pub fn run(self) {
+    pub fn run(mut self) {
         // kinda a fail safe thing - avoids clogging logs
         let mut error_count = 0;
         loop {
-            match LServer::read() {
+            let message = match self.queued.pop_front() {
+                Some(message) => message,
+                None => self.read(),
+            };
+            match message {
                 Ok(message) => {
                     error_count = 0;
                     match message {
                         LSMessage::Request(request) => {
                             let request_body = request.request;
-                            match self.message_response(request_body) {
+                            let method = request_body.method;
+                            let params = request_body.params;
+                            let id = request.id;
+                            self.client.log_trace(
+                                format!("Received request '{method} - ({id})'."),
+                                || format!("Params: {}", json_or_none(&params)),
+                            );
+                            let started_at = Instant::now();
+                            let response = match self.skipped(&id, &method, params.as_ref()) {
+                                Some(err) => Err(err),
+                                None => self.catch_panic(&method, |server| {
+                                    server.message_response(&method, params)
+                                }),
+                            };
+                            match response {
                                 Ok(response) => {
-                                    let id = request.id;
+                                    self.client.log_trace(
+                                        format!(
+                                            "Sending response '{method} - ({id})'. \
+                                            Processing request took {}ms",
+                                            started_at.elapsed().as_millis()
+                                        ),
+                                        || format!("Result: {response}"),
+                                    );
                                     let response = LSMessageResponse::new(id, response);
-                                    self.respond(&response);
+                                    self.client.send(&response);
                                 }
                                 Err(err) => {
-                                    self.respond_with_error(LSMessageError::new(
-                                        request.id,
+                                    self.client.log_trace(
+                                        format!(
+                                            "Sending response '{method} - ({id})'. \
+                                            Processing request failed after {}ms",
+                                            started_at.elapsed().as_millis()
+                                        ),
+                                        || format!("Error: {err}"),
+                                    );
+                                    self.client.send(&LSMessageError::new(
+                                        Some(id),
                                         LSMessageErrorBody::from(err),
                                     ));
                                 }
                             }
                         }
-                        LSMessage::Notification(notification) => match notification.notification {
-                            LSMessageNotificationBody::Initialized {} => {
-                                debug!("initialized!");
-                            }
-                            LSMessageNotificationBody::Exit => {
+                        LSMessage::Notification(notification) => {
+                            let notification = notification.notification;
+                            if notification.method == notification::Exit::METHOD {
                                 break;
                             }
-                        },
-                        _ => todo!(),
+                            let method = notification.method;
+                            let params = notification.params;
+                            self.client
+                                .log_trace(format!("Received notification '{method}'."), || {
+                                    format!("Params: {}", json_or_none(&params))
+                                });
+                            if let Err(err) = self.catch_panic(&method, |server| {
+                                server.handle_notification(&method, params)
+                            }) {
+                                debug!("Error handling notification: {err}");
+                            }
+                        }
+                        LSMessage::Response(response) => self.handle_response(response),
                     }
                 }
+                Err(err @ ParseError::JsonParsing(_)) => {
+                    // the message never made it to a JSON value,
+                    // so there's no id to reply to
+                    self.client.send(&LSMessageError::new(
+                        None,
+                        LSMessageErrorBody::from(LSError::ParseError(err)),
+                    ));
+                }
+                Err(ParseError::InvalidMessage { id, error }) => {
+                    debug!("Invalid message: {error}");
+                    self.client.send(&LSMessageError::new(
+                        id,
+                        LSMessageErrorBody::from(LSError::InvalidRequest {
+                            message: format!("{error}"),
+                        }),
+                    ));
+                }
+                Err(ParseError::Eof) => {
+                    debug!("Input closed");
+                    break;
+                }
                 Err(err) => {
                     error_count += 1;
                     debug!("Error: {err:?}");
@@ -267,377 +274,892 @@ impl LServer {
         debug!("exiting");
     }
 
-    #[instrument]
-    fn read() -> Result<LSMessage, ParseError> {
-        let mut buf = String::new();
+    #[instrument(skip(self))]
+    fn read(&mut self) -> Result<LSMessage, ParseError> {
         let mut content_length = None;
         loop {
             debug!("Waiting for input");
-            io::stdin()
-                .read_line(&mut buf)
-                .map_err(|err| ParseError::Io(err))?;
-
-            if buf.len() == 0 {
-                break;
+            let mut line = String::new();
+            if self.input.read_line(&mut line).map_err(ParseError::Io)? == 0 {
+                return Err(ParseError::Eof);
             }
-            if buf == "\r\n" {
+            // an empty line ends the headers
+            if line == "\r\n" {
                 break;
             }
-            let (name, value) = buf.split_once(":").ok_or_else(|| ParseError::Header)?;
+            let (name, value) = line.split_once(":").ok_or(ParseError::Header)?;
             debug!("got header: '{:?}': '{:?}'", name, value);
-            if name == "Content-Length" {
+            if name.eq_ignore_ascii_case("Content-Length") {
                 content_length = Some(value.trim().parse().map_err(|_e| ParseError::Header)?);
             }
-            if buf.ends_with("\r\n\r\n") {
-                break;
-            }
         }
 
-        let content_length = content_length.ok_or_else(|| ParseError::Header)?;
+        let content_length = content_length.ok_or(ParseError::Header)?;
         let header = LSHeader { content_length };
         let mut buf = vec![0u8; header.content_length as usize];
-        io::stdin()
-            .read_exact(&mut buf)
-            .map_err(|err| ParseError::Io(err))?;
+        self.input.read_exact(&mut buf).map_err(ParseError::Io)?;
         let content = String::from_utf8_lossy(&buf);
         // debug!("content-raw: {}", content);
-        let content: LSMessage = serde_json::from_str(&content)
-            .map_err(|e| ParseError::JsonParsing((e, content.to_string())))?;
+        // parsing in two steps lets us tell malformed JSON (-32700) apart from
+        // well-formed JSON that isn't a valid message (-32600), and in the
+        // latter case still reply with the request's id
+        let value: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                self.client.record(Direction::In, || {
+                    serde_json::Value::String(content.to_string())
+                });
+                return Err(ParseError::JsonParsing((e, content.to_string())));
+            }
+        };
+        self.client.record(Direction::In, || value.clone());
+        let id = value
+            .get("id")
+            .and_then(|id| serde_json::from_value(id.clone()).ok());
+        let content: LSMessage = serde_json::from_value(value)
+            .map_err(|error| ParseError::InvalidMessage { id, error })?;
         debug!("content: {:?}", content);
 
         Ok(content)
     }
 
-    fn respond_with_error(&self, response: LSMessageError) {
-        let response = serde_json::to_string(&response).unwrap();
-        let content_length = response.len();
-        let response = format!(
-            "Content-Length: {content_length}\r\nContent-Length: {content_length}\r\n\r\n{response}"
-        );
-        debug!("respond with error: {:?}", response);
-        println!("{}", response)
+    /// Why the request `id` isn't to be handled, if it isn't: it came before
+    /// `initialize` or after `shutdown`, or the messages that already arrived
+    /// after it cancel it or change the document it's about
+    fn skipped(
+        &mut self,
+        id: &JsonRpcRequestId,
+        method: &str,
+        params: Option<&serde_json::Value>,
+    ) -> Option<LSError> {
+        if self.shut_down {
+            return Some(LSError::InvalidRequest {
+                message: format!("'{method}' after shutdown"),
+            });
+        }
+        if !self.initialized && method != request::Initialize::METHOD {
+            return Some(LSError::ServerNotInitialized);
+        }
+        self.read_buffered();
+        let queued_notification =
+            |message: &Result<LSMessage, ParseError>, queued_method| match message {
+                Ok(LSMessage::Notification(notification))
+                    if notification.notification.method == queued_method =>
+                {
+                    notification.notification.params.clone()
+                }
+                _ => None,
+            };
+        let cancel = self.queued.iter().position(|message| {
+            queued_notification(message, notification::Cancel::METHOD)
+                .and_then(|params| serde_json::from_value(params["id"].clone()).ok())
+                .is_some_and(|cancelled: JsonRpcRequestId| cancelled == *id)
+        });
+        if let Some(cancel) = cancel {
+            self.queued.remove(cancel);
+            return Some(LSError::RequestCancelled);
+        }
+        let document = params.map(|params| &params["textDocument"]["uri"])?;
+        let modified = self.queued.iter().any(|message| {
+            queued_notification(message, notification::DidChangeTextDocument::METHOD)
+                .is_some_and(|params| params["textDocument"]["uri"] == *document)
+        });
+        modified.then_some(LSError::ContentModified)
     }
 
-    fn respond(&self, response: &LSMessageResponse) {
-        let response = serde_json::to_string(&response).unwrap();
-        let content_length = response.len();
-        let response = format!(
-            "Content-Length: {content_length}\r\nContent-Length: {content_length}\r\n\r\n{response}"
-        );
-        debug!("respond: {:?}", response);
-        println!("{}", response)
+    /// Reads the messages that already arrived, without waiting for more
+    fn read_buffered(&mut self) {
+        while !self.input.buffer().is_empty() {
+            let message = self.read();
+            self.queued.push_back(message);
+        }
     }
 
-    fn message_response(&self, request: LSMessageRequestBody) -> LSResult<LSMessageResponseBody> {
-        match request {
-            LSMessageRequestBody::Initialize { capabilities } => {
-                Ok(LSMessageResponseBody::Initialize(
-                    LSMessageResponseInitialize::new("myls", "0.0.1", capabilities),
-                ))
-            }
-            LSMessageRequestBody::TextDocumentDefinition {
-                position,
-                text_document,
-            } => {
-                debug!(
-                    "textDocument/definition recieved at position {position:?} in file: '{}'",
-                    text_document.uri
-                );
-                let uri = text_document.uri;
-                let controller_uris = self.get_controller_possible_uris(&uri);
-                if controller_uris.is_empty() {
-                    return Ok(LSMessageResponseBody::RawType(LsType::Null));
+    /// Runs `handler`, turning a panic inside it into an `InternalError`
+    /// so that one bad message doesn't take the whole server down
+    fn catch_panic<T>(
+        &mut self,
+        method: &str,
+        handler: impl FnOnce(&mut Self) -> LSResult<T>,
+    ) -> LSResult<T> {
+        match panic::catch_unwind(AssertUnwindSafe(|| handler(self))) {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = panic_message(payload.as_ref());
+                error!("'{method}' panicked: {message}");
+                self.panic_count += 1;
+                if self.panic_count.is_multiple_of(PANIC_REPORT_THRESHOLD) {
+                    self.client.notify::<notification::ShowMessage>(ShowMessageParams {
+                        typ: MessageType::ERROR,
+                        message: format!(
+                            "myls: {} requests have crashed so far, the last one was '{method}'. \
+                            Check the server logs for details.",
+                            self.panic_count
+                        ),
+                    });
                 }
-                let file_path = self.path_from_uri(&uri)?;
-                let html_contents =
-                    fs::read_to_string(file_path).map_err(|e| LSError::InvalidRequest {
-                        message: format!("Couldn't read HTML: {e}"),
-                    })?;
-                let ts_contents = self.get_first_opening_file(controller_uris);
-                if ts_contents.is_none() {
-                    return Ok(LSMessageResponseBody::RawType(LsType::Null));
-                }
-                let (ts_file_uri, ts_contents) = ts_contents.unwrap();
-                debug!("TS URI: {ts_file_uri},TS contents: {ts_contents}");
-                let mut parser = tree_sitter::Parser::new();
-                parser
-                    .set_language(&tree_sitter_html::LANGUAGE.into())
-                    .map_err(LSError::internal)?;
-                let tree = parser.parse(&html_contents, None).ok_or_else(|| {
-                    LSError::ParseError(ParseError::DocumentParsing { file: uri.clone() })
-                })?;
+                Err(LSError::InternalError {
+                    message: format!("'{method}' panicked: {message}"),
+                })
+            }
+        }
+    }
 
-                let mut cursor = tree.walk();
-                // cursor.node();
-                while let Some(_child_index) = cursor.goto_first_child_for_point(
-                    tree_sitter::Point::new(position.line as usize, position.character as usize),
-                ) {}
-                let node = cursor.node();
-                let text = node.utf8_text(&html_contents.as_bytes()).map_err(|_e| {
-                    LSError::ParseError(ParseError::DocumentParsing { file: uri.clone() })
-                })?;
-                let start_column = node.start_position().column;
-                let cursor_at = position.character as usize - start_column;
-                debug!("cursor is at {cursor_at}: '{}'", &text[cursor_at..]);
-                debug!("node={node:?}");
-                let mut js_parser = tree_sitter::Parser::new();
-                js_parser
-                    .set_language(&tree_sitter_javascript::LANGUAGE.into())
-                    .map_err(LSError::internal)?;
-                let tree = js_parser.parse(text, None).ok_or_else(|| {
-                    LSError::ParseError(ParseError::DocumentParsing { file: uri.clone() })
-                })?;
-                let sexp = tree.root_node().to_sexp();
-                debug!("sexp={sexp}");
-                let query_controller_exp = r#"
-                (member_expression
-                    object: (identifier) @obj (#eq? @obj "vm")
-                    property: (property_identifier) @method
-                )"#;
-                let query = Query::new(
-                    &tree_sitter_javascript::LANGUAGE.into(),
-                    query_controller_exp,
-                );
-                if let Err(err) = query {
-                    debug!("qyery error {err:?}");
-                    return Ok(LSMessageResponseBody::RawType(LsType::Null));
+    fn message_response(
+        &mut self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> LSResult<serde_json::Value> {
+        match method {
+            request::Initialize::METHOD => {
+                self.handle::<request::Initialize>(params, Self::initialize)
+            }
+            request::GotoDefinition::METHOD => self
+                .handle::<request::GotoDefinition>(params, |server, params| {
+                    server.definition(params)
+                }),
+            request::Completion::METHOD => self
+                .handle::<request::Completion>(params, |server, params| server.completion(params)),
+            request::HoverRequest::METHOD => {
+                self.handle::<request::HoverRequest>(params, |server, params| server.hover(params))
+            }
+            request::References::METHOD => self
+                .handle::<request::References>(params, |server, params| server.references(params)),
+            request::DocumentLinkRequest::METHOD => self
+                .handle::<request::DocumentLinkRequest>(params, |server, params| {
+                    server.document_link(params)
+                }),
+            protocol::myls::GoToTemplate::METHOD => self
+                .handle::<protocol::myls::GoToTemplate>(params, |server, params| {
+                    server.go_to_template(params)
+                }),
+            protocol::myls::GoToController::METHOD => self
+                .handle::<protocol::myls::GoToController>(params, |server, params| {
+                    server.go_to_controller(params)
+                }),
+            request::Shutdown::METHOD => self.handle::<request::Shutdown>(params, |server, ()| {
+                server.shut_down = true;
+                Ok(())
+            }),
+            _ => {
+                debug!("Unknown request: {}. params={:?}", method, params);
+                Err(LSError::MethodNotFound(method.to_owned()))
+            }
+        }
+    }
+
+    /// Deserializes `params` into the params type of the request `R`,
+    /// runs `handler` with them and serializes the result back
+    fn handle<R: Request>(
+        &mut self,
+        params: Option<serde_json::Value>,
+        handler: impl FnOnce(&mut Self, R::Params) -> LSResult<R::Result>,
+    ) -> LSResult<serde_json::Value>
+    where
+        R::Params: DeserializeOwned,
+        R::Result: Serialize,
+    {
+        let params = serde_json::from_value(params.unwrap_or(serde_json::Value::Null))
+            .map_err(LSError::invalid_params)?;
+        let result = handler(self, params)?;
+        serde_json::to_value(result).map_err(LSError::internal)
+    }
+
+    fn handle_notification(
+        &mut self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> LSResult<()> {
+        match method {
+            notification::Initialized::METHOD => {
+                debug!("initialized!");
+                if self.settings.pull_client {
+                    self.pull_configuration();
                 }
-                let query = query.unwrap();
-                let mut cursor = QueryCursor::new();
-                let mut matches = cursor.matches(&query, tree.root_node(), text.as_bytes());
-                while let Some(m) = matches.next() {
-                    // let obj_name = m.captures[0]
-                    //     .node
-                    //     .utf8_text(text.as_bytes())
-                    //     .map_err(|_e| {
-                    //         LSError::ParseError(ParseError::DocumentParsing { file: uri.clone() })
-                    //     })?;
-                    let prop_name =
-                        m.captures[1]
-                            .node
-                            .utf8_text(text.as_bytes())
-                            .map_err(|_e| {
-                                LSError::ParseError(ParseError::DocumentParsing {
-                                    file: uri.clone(),
-                                })
-                            })?;
-                    // if obj_name == "vm" {
-                    debug!("found vm with prop={prop_name}");
-                    let mut parser = tree_sitter::Parser::new();
-                    let _ =
-                        parser.set_language(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into());
-                    let tree = parser.parse(&ts_contents, None);
-                    if tree.is_none() {
-                        continue;
-                    }
-                    let tree = tree.unwrap();
-                    let sexp = tree.root_node().to_sexp();
-                    debug!("ts sexp={sexp:?}");
-                    let query_field_def = format!(
-                        "
-                        (
-                            public_field_definition
-                                name: (property_identifier) @prop
-                        )
-                       "
-                    );
-                    debug!("query={query_field_def}");
-                    let query = Query::new(
-                        &tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
-                        &query_field_def,
+                Ok(())
+            }
+            notification::DidChangeConfiguration::METHOD => {
+                self.notify::<notification::DidChangeConfiguration>(params, |server, params| {
+                    server.did_change_configuration(params);
+                    Ok(())
+                })
+            }
+            notification::DidChangeWorkspaceFolders::METHOD => {
+                self.notify::<notification::DidChangeWorkspaceFolders>(params, |server, params| {
+                    server.did_change_workspace_folders(params);
+                    Ok(())
+                })
+            }
+            notification::DidChangeWatchedFiles::METHOD => self
+                .notify::<notification::DidChangeWatchedFiles>(params, |server, params| {
+                    server.did_change_watched_files(params);
+                    Ok(())
+                }),
+            notification::Cancel::METHOD => {
+                // requests are cancelled before they're handled, see `Self::skipped`
+                debug!("Request already answered, not cancelling it");
+                Ok(())
+            }
+            notification::SetTrace::METHOD => {
+                self.notify::<notification::SetTrace>(params, |server, params| {
+                    server.client.set_trace(params.value);
+                    Ok(())
+                })
+            }
+            notification::DidOpenTextDocument::METHOD => self
+                .notify::<notification::DidOpenTextDocument>(params, |server, params| {
+                    let document = params.text_document;
+                    let uri = DocumentUri::try_from(&document.uri)?;
+                    let workspace = &mut server.projects.owner_mut(&uri).workspace;
+                    workspace.forget(&uri);
+                    workspace
+                        .documents
+                        .open(uri.clone(), document.text, document.version);
+                    server.refresh_diagnostics(&uri);
+                    Ok(())
+                }),
+            notification::DidChangeTextDocument::METHOD => self
+                .notify::<notification::DidChangeTextDocument>(params, |server, params| {
+                    let uri = DocumentUri::try_from(&params.text_document.uri)?;
+                    let encoding = server.projects.encoding();
+                    let workspace = &mut server.projects.owner_mut(&uri).workspace;
+                    workspace.forget(&uri);
+                    workspace.documents.change(
+                        &uri,
+                        params.text_document.version,
+                        params.content_changes,
+                        encoding,
                     );
-                    if let Err(err) = query {
-                        debug!("TS query error: {err}");
-                        return Ok(LSMessageResponseBody::RawType(LsType::Null));
-                    }
-                    let query = query.unwrap();
-                    let mut cursor = QueryCursor::new();
-                    let mut matches =
-                        cursor.matches(&query, tree.root_node(), ts_contents.as_bytes());
-                    while let Some(m) = matches.next() {
-                        debug!("processing match");
-                        let node = m.captures[0].node;
-                        let start = node.start_position();
-                        let end = node.start_position();
-                        return Ok(LSMessageResponseBody::Location(
-                            LSMessageResponseLocation::new(
-                                ts_file_uri.to_string(),
-                                LsTypeRange::range(
-                                    (start.row, start.column),
-                                    (end.row, end.column),
-                                ),
-                            ),
-                        ));
+                    server.refresh_diagnostics(&uri);
+                    Ok(())
+                }),
+            notification::DidCloseTextDocument::METHOD => self
+                .notify::<notification::DidCloseTextDocument>(params, |server, params| {
+                    let uri = DocumentUri::try_from(&params.text_document.uri)?;
+                    let workspace = &mut server.projects.owner_mut(&uri).workspace;
+                    workspace.forget(&uri);
+                    workspace.documents.close(&uri);
+                    server.refresh_diagnostics(&uri);
+                    Ok(())
+                }),
+            _ => {
+                debug!("Unknown notification: {}. params={:?}", method, params);
+                Ok(())
+            }
+        }
+    }
+
+    /// Like [`Self::handle`] but for notifications, which have no result
+    fn notify<N: Notification>(
+        &mut self,
+        params: Option<serde_json::Value>,
+        handler: impl FnOnce(&mut Self, N::Params) -> LSResult<()>,
+    ) -> LSResult<()>
+    where
+        N::Params: DeserializeOwned,
+    {
+        let params = serde_json::from_value(params.unwrap_or(serde_json::Value::Null))
+            .map_err(LSError::invalid_params)?;
+        handler(self, params)
+    }
+
+    fn initialize(&mut self, params: InitializeParams) -> LSResult<InitializeResult> {
+        if let Some(trace) = params.trace {
+            self.client.set_trace(trace);
+        }
+        self.projects.set_encoding(PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|general| general.position_encodings.as_deref()),
+        ));
+        debug!(
+            "negotiated position encoding: {:?}",
+            self.projects.encoding()
+        );
+        self.settings.initialization_options = params.initialization_options.unwrap_or_default();
+        self.settings.pull_client = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.configuration)
+            .unwrap_or(false);
+        // clients that don't know about folders only send the root
+        #[allow(deprecated)]
+        let folders = params.workspace_folders.unwrap_or_else(|| {
+            params
+                .root_uri
+                .into_iter()
+                .map(|uri| WorkspaceFolder {
+                    name: folder_name(&uri),
+                    uri,
+                })
+                .collect()
+        });
+        self.projects.change_folders(folders, &[]);
+        self.reload_config();
+        let capabilities = ServerCapabilities {
+            position_encoding: Some(self.projects.encoding().kind()),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
+            definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            document_link_provider: Some(DocumentLinkOptions {
+                resolve_provider: Some(false),
+                work_done_progress_options: Default::default(),
+            }),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec!["<".to_owned(), "|".to_owned()]),
+                ..Default::default()
+            }),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: None,
+            }),
+            ..Default::default()
+        };
+        self.initialized = true;
+        Ok(InitializeResult {
+            capabilities,
+            server_info: Some(ServerInfo {
+                name: "myls".to_owned(),
+                version: Some("0.0.1".to_owned()),
+            }),
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> LSResult<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params.position;
+        let text_document = params.text_document_position_params.text_document;
+        debug!(
+            "textDocument/definition recieved at position {position:?} in file: '{}'",
+            text_document.uri.as_str()
+        );
+        let uri = DocumentUri::try_from(&text_document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        if workspace.documents.get_or_read(&uri).is_none() {
+            return Err(LSError::RequestFailed {
+                message: format!("Couldn't read HTML: '{uri}'"),
+                data: Some(serde_json::json!({ "uri": text_document.uri })),
+            });
+        }
+        Ok(workspace
+            .resolve_definition(&text_document.uri, position)
+            .map(GotoDefinitionResponse::Scalar))
+    }
+
+    fn completion(&self, params: CompletionParams) -> LSResult<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let uri = DocumentUri::try_from(&position.text_document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        Ok(Some(CompletionResponse::Array(workspace.completions(
+            &position.text_document.uri,
+            position.position,
+        ))))
+    }
+
+    fn document_link(&self, params: DocumentLinkParams) -> LSResult<Option<Vec<DocumentLink>>> {
+        let uri = DocumentUri::try_from(&params.text_document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        Ok(Some(workspace.document_links(&params.text_document.uri)))
+    }
+
+    fn go_to_template(&self, document: TextDocumentIdentifier) -> LSResult<Option<Location>> {
+        let uri = DocumentUri::try_from(&document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        Ok(workspace.template_of(&document.uri))
+    }
+
+    fn go_to_controller(&self, document: TextDocumentIdentifier) -> LSResult<Option<Location>> {
+        let uri = DocumentUri::try_from(&document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        Ok(workspace.controller_of(&document.uri))
+    }
+
+    fn hover(&self, params: HoverParams) -> LSResult<Option<Hover>> {
+        let position = params.text_document_position_params.position;
+        let uri = DocumentUri::try_from(&params.text_document_position_params.text_document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        let Some(document) = workspace.documents.get_or_read(&uri) else {
+            return Ok(None);
+        };
+        let offset = document.offset(position, workspace.encoding);
+        let Some(document) = embedded::template_at(&document, offset, workspace) else {
+            return Ok(None);
+        };
+        if let Some(hover) = directive_hover(&document, offset, workspace)
+            .or_else(|| filter_hover(&document, offset, workspace))
+        {
+            return Ok(Some(hover));
+        }
+        let Some(reference) = navigation::find_controller_reference(&document, offset, workspace)
+        else {
+            return Ok(None);
+        };
+        let Some((controller, member)) =
+            navigation::resolve_member(&document, &reference, workspace)
+        else {
+            return Ok(None);
+        };
+        // the line the member is declared on is its signature, more or less
+        let text = controller.text();
+        let line_start = text[..member.range.start]
+            .rfind('\n')
+            .map_or(0, |at| at + 1);
+        let line_end = text[member.range.start..]
+            .find('\n')
+            .map_or(text.len(), |at| member.range.start + at);
+        let declaration = text[line_start..line_end].trim();
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```typescript\n{declaration}\n```"),
+            }),
+            range: Some(document.range(
+                reference.range.start,
+                reference.range.end,
+                workspace.encoding,
+            )),
+        }))
+    }
+
+    fn references(&self, params: ReferenceParams) -> LSResult<Option<Vec<Location>>> {
+        let position = params.text_document_position.position;
+        let uri = DocumentUri::try_from(&params.text_document_position.text_document.uri)?;
+        let workspace = &self.projects.owner(&uri).workspace;
+        let document =
+            workspace
+                .documents
+                .get_or_read(&uri)
+                .ok_or_else(|| LSError::RequestFailed {
+                    message: format!("Couldn't read '{uri}'"),
+                    data: Some(serde_json::json!({ "uri": uri.to_string() })),
+                })?;
+        let offset = document.offset(position, workspace.encoding);
+        let locations = navigation::find_references(
+            &document,
+            offset,
+            params.context.include_declaration,
+            workspace,
+        )
+        .into_iter()
+        .map(|(document, range)| {
+            Ok(Location::new(
+                Uri::try_from(&document.uri)?,
+                document.range(range.start, range.end, workspace.encoding),
+            ))
+        })
+        .collect::<LSResult<Vec<_>>>()?;
+        Ok(Some(locations))
+    }
+
+    /// Sends the diagnostics of the template at `uri`, or of the templates
+    /// embedded in the script at `uri`, or clears them when it's no longer open
+    fn publish_diagnostics(&self, uri: &DocumentUri) {
+        let workspace = &self.projects.owner(uri).workspace;
+        if !workspace.has_diagnostics(uri) {
+            return;
+        }
+        let Ok(lsp_uri) = Uri::try_from(uri) else {
+            return;
+        };
+        let (diagnostics, version) = match workspace.documents.get(uri) {
+            Some(document) => (
+                diagnostics::template_diagnostics(document, workspace),
+                document.version,
+            ),
+            None => (vec![], None),
+        };
+        self.client
+            .notify::<notification::PublishDiagnostics>(PublishDiagnosticsParams {
+                uri: lsp_uri,
+                diagnostics,
+                version,
+            });
+    }
+
+    /// Publishes the diagnostics of what's affected by a change to `uri`:
+    /// the template itself, or every open document when a script changed
+    fn refresh_diagnostics(&self, uri: &DocumentUri) {
+        let workspace = &self.projects.owner(uri).workspace;
+        if workspace.is_script(uri) {
+            // a script that was just closed isn't among them, to be cleared
+            if workspace.documents.get(uri).is_none() {
+                self.publish_diagnostics(uri);
+            }
+            for document in workspace.documents.iter() {
+                self.publish_diagnostics(&document.uri);
+            }
+        } else {
+            self.publish_diagnostics(uri);
+        }
+    }
+
+    /// Handles the client's answer to a request the server sent
+    fn handle_response(&mut self, response: JsonRpcGenericResponse) {
+        match response {
+            JsonRpcGenericResponse::Result { id, result } => {
+                match self.pending_requests.remove(&id) {
+                    Some(PendingRequest::Configuration(scopes)) => {
+                        // one result per item asked for, in the same order
+                        let results: Vec<serde_json::Value> =
+                            serde_json::from_value(result).unwrap_or_default();
+                        for (scope, settings) in scopes.iter().zip(results) {
+                            if let Some(project) = self
+                                .projects
+                                .iter_mut()
+                                .find(|project| project.scope() == scope.as_ref())
+                            {
+                                project.client_settings = settings;
+                            }
+                        }
+                        self.reload_config();
                     }
-                    // }
+                    None => debug!("Response to an unknown request: ({id})"),
                 }
-                // debug!("method={method:?}");
-                // let query_extract_member_var = r#"
-                //     (member_expression {object = })
-                //     "#;
-                // let cursor = tree.walk();
-                Ok(LSMessageResponseBody::RawType(LsType::Null))
-                // Ok(LSMessageResponseBody::Location(
-                //     LSMessageResponseLocation::new(uri, LsTypeRange::beginning()),
-                // ))
             }
-            LSMessageRequestBody::Shutdown => Ok(LSMessageResponseBody::Shutdown),
-            LSMessageRequestBody::Unknown { method, params } => {
-                debug!("Unknown request: {}. params={:?}", method, params);
-                Err(LSError::MethodNotFound(method))
+            JsonRpcGenericResponse::Error { id, error } => {
+                let request = id.and_then(|id| self.pending_requests.remove(&id));
+                debug!("Request {request:?} failed: {error:?}");
             }
         }
     }
 
-    fn get_first_opening_file<'a>(&self, uris: Vec<String>) -> Option<(String, String)> {
-        for (uri, path) in uris
+    /// Asks the client for the settings of each folder, which are applied
+    /// once it answers
+    fn pull_configuration(&mut self) {
+        let scopes: Vec<Option<Uri>> = self
+            .projects
             .iter()
-            .map(|uri| self.path_from_uri(uri).map(|path| (uri, path)))
-            .flatten()
+            .map(|project| project.scope().cloned())
+            .collect();
+        let params = ConfigurationParams {
+            items: scopes
+                .iter()
+                .map(|scope| ConfigurationItem {
+                    scope_uri: scope.clone(),
+                    section: Some(CONFIG_SECTION.to_owned()),
+                })
+                .collect(),
+        };
+        if let Some(id) = self
+            .client
+            .request::<request::WorkspaceConfiguration>(params)
         {
-            if let Ok(contents) = fs::read_to_string(path) {
-                return Some((uri.clone(), contents));
-            }
+            self.pending_requests
+                .insert(id, PendingRequest::Configuration(scopes));
         }
-        return None;
-    }
-
-    fn get_controller_possible_uris(&self, uri: &str) -> Vec<String> {
-        uri.strip_suffix(".html")
-            .and_then(|uri| uri.rsplit_once("/"))
-            .map(|(pre, filename)| {
-                let pascalified = filename
-                    .split("-")
-                    .map(|part| {
-                        let mut chars = part.chars();
-                        if let Some(first_char) = chars.next() {
-                            let rest: String = chars.collect();
-                            format!("{}{}", first_char.to_uppercase(), rest)
-                        } else {
-                            part.to_string()
-                        }
-                    })
-                    .collect::<String>();
-                ["Controller", "Directive", ""]
-                    .map(|ending| format!("{pre}/{pascalified}{ending}.ts",))
-                    .to_vec()
-            })
-            .unwrap_or(vec![])
-    }
-
-    fn path_from_uri<'a>(&self, uri: &'a str) -> LSResult<&'a str> {
-        if let Some(path) = uri.strip_prefix("file://") {
-            Ok(path)
+    }
+
+    fn did_change_configuration(&mut self, params: DidChangeConfigurationParams) {
+        if self.settings.pull_client {
+            // the settings sent along may be any section or none at all
+            self.pull_configuration();
         } else {
-            todo!()
+            let settings = params
+                .settings
+                .get(CONFIG_SECTION)
+                .cloned()
+                .unwrap_or_default();
+            for project in self.projects.iter_mut() {
+                project.client_settings = settings.clone();
+            }
+            self.reload_config();
         }
     }
-}
 
-struct LSHeader {
-    content_length: u32,
-}
-
-type LSResult<T> = Result<T, LSError>;
-
-#[derive(Error, Debug)]
-enum LSError {
-    // #[error("Parse error")]
-    // Parse(ParseError),
-    #[error("Internal error: {message}")]
-    InternalError { message: String },
-    #[error("Invalid request: {message}")]
-    InvalidRequest { message: String },
-    #[error("Method not found: '{0}'")]
-    MethodNotFound(String),
-    #[error("Parsing error: '{0}'")]
-    ParseError(ParseError),
-}
+    fn did_change_workspace_folders(&mut self, params: DidChangeWorkspaceFoldersParams) {
+        self.projects
+            .change_folders(params.event.added, &params.event.removed);
+        if self.settings.pull_client {
+            self.pull_configuration();
+        }
+        self.update_config();
+        // open documents may have moved to another project
+        let uris: Vec<DocumentUri> = self
+            .projects
+            .iter()
+            .flat_map(|project| project.workspace.documents.iter())
+            .map(|document| document.uri.clone())
+            .collect();
+        for uri in uris {
+            self.publish_diagnostics(&uri);
+        }
+    }
 
-impl LSError {
-    fn code(&self) -> i32 {
-        match self {
-            LSError::InternalError { message: _ } => -32603,
-            LSError::InvalidRequest { message: _ } => -32600,
-            LSError::MethodNotFound(_) => -32601,
-            LSError::ParseError(_) => -32700,
+    /// Forgets what's known of the scripts that changed on disk and
+    /// republishes the diagnostics of the open documents, which may use them.
+    /// The config is reloaded instead if a config file changed
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) {
+        let mut scripts_changed = false;
+        for change in &params.changes {
+            let Ok(uri) = DocumentUri::try_from(&change.uri) else {
+                continue;
+            };
+            scripts_changed |= self.projects.owner(&uri).workspace.is_script(&uri);
+            // a project without a root looks for scripts next to its templates,
+            // which may be in another project's folder
+            for project in self.projects.iter() {
+                project.workspace.forget(&uri);
+                if change.typ != FileChangeType::CHANGED {
+                    project.workspace.forget_listings();
+                }
+            }
+        }
+        let config_files: Vec<PathBuf> = self
+            .projects
+            .iter()
+            .filter_map(|project| config_file(&self.settings, project))
+            .collect();
+        let config_changed = params.changes.iter().any(|change| {
+            DocumentUri::try_from(&change.uri)
+                .ok()
+                .and_then(|uri| uri.to_file_path())
+                .is_some_and(|path| config_files.contains(&path))
+        });
+        if config_changed {
+            self.reload_config();
+        } else if scripts_changed {
+            let uris: Vec<DocumentUri> = self
+                .projects
+                .iter()
+                .flat_map(|project| project.workspace.documents.iter())
+                .map(|document| document.uri.clone())
+                .collect();
+            for uri in uris {
+                self.publish_diagnostics(&uri);
+            }
         }
     }
-    fn message(&self) -> String {
-        self.to_string()
+
+    /// Merges the config of each project again from all its layers and
+    /// republishes the diagnostics of the open templates of those that changed
+    fn reload_config(&mut self) {
+        for uri in self.update_config() {
+            self.publish_diagnostics(&uri);
+        }
     }
 
-    fn internal<E: std::error::Error>(e: E) -> Self {
-        Self::InternalError {
-            message: format!("{e}"),
+    /// Merges the config of each project again from all its layers, returning
+    /// the open documents of the projects whose config changed. An invalid
+    /// config is reported to the user and the previous one is kept
+    fn update_config(&mut self) -> Vec<DocumentUri> {
+        let mut errors = vec![];
+        let mut changed = vec![];
+        for project in self.projects.iter_mut() {
+            let config = match load_config(&self.settings, project) {
+                Ok(config) if config == project.workspace.config => continue,
+                Ok(config) => config,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            debug!("config of {:?}: {config:?}", project.root());
+            match project.workspace.set_config(config) {
+                Ok(()) => changed.extend(
+                    project
+                        .workspace
+                        .documents
+                        .iter()
+                        .map(|document| document.uri.clone()),
+                ),
+                Err(err) => errors.push(err),
+            }
+        }
+        // the same settings usually break every project, once is enough
+        if let Some(err) = errors.first() {
+            self.report_config_error(err);
         }
+        changed
     }
+
+    fn report_config_error(&self, err: &ConfigError) {
+        error!("Couldn't load the config: {err}");
+        self.client
+            .notify::<notification::ShowMessage>(ShowMessageParams {
+                typ: MessageType::WARNING,
+                message: format!("myls: couldn't load the config, {err}"),
+            });
+    }
+}
+
+/// The project config file of `project`, whether it exists or not
+fn config_file(settings: &Settings, project: &Project) -> Option<PathBuf> {
+    settings
+        .file
+        .clone()
+        .or_else(|| project.root().map(|root| root.join(CONFIG_FILE_NAME)))
 }
 
-#[derive(Error, Debug)]
-enum ParseError {
-    #[error("Couldn't parse '{file}'")]
-    DocumentParsing { file: String },
-    #[error("Header invalid")]
-    Header,
-    #[error("IO error while parsing")]
-    Io(#[from] io::Error),
-    #[error("JSON parsing error. e: {}", .0.0)]
-    JsonParsing((serde_json::Error, String)),
+fn load_config(settings: &Settings, project: &Project) -> Result<Config, ConfigError> {
+    let file = match config_file(settings, project) {
+        Some(path) if settings.file.is_some() || path.is_file() => config::read_layer(&path)?,
+        _ => serde_json::Value::Null,
+    };
+    Config::from_layers([
+        &settings.initialization_options,
+        &project.client_settings,
+        &file,
+    ])
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcMessageBase {
-    /// 2.0
-    jsonrpc: String,
+/// Documentation of the built-in directive whose attribute name is at
+/// `offset` of the template `document`
+fn directive_hover(document: &Document, offset: usize, workspace: &Workspace) -> Option<Hover> {
+    let template = Template::parse(document, workspace);
+    let attribute = template.attribute_at(offset)?;
+    let directive = directives::ng_directive(&attribute.directive_name())?;
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: directive.documentation(),
+        }),
+        range: Some(document.range(
+            attribute.name_range.start,
+            attribute.name_range.end,
+            workspace.encoding,
+        )),
+    })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcError<ErrorBody> {
-    id: JsonRpcRequestId,
-    error: ErrorBody,
-    #[serde(flatten)]
-    base: JsonRpcMessageBase,
+/// How the filter whose name is at `offset` of the template `document` is
+/// used, and what it does for the built-in ones
+fn filter_hover(document: &Document, offset: usize, workspace: &Workspace) -> Option<Hover> {
+    let template = Template::parse(document, workspace);
+    let range = template.filter_at(document.text(), offset)?;
+    let name = &document.text()[range.clone()];
+    let value = match filters::ng_filter(name) {
+        Some(filter) => format!(
+            "```\n{}\n```\n\n{}",
+            filters::usage(name, filter.parameters),
+            filter.summary
+        ),
+        None => {
+            let (_, filter) = navigation::find_filter(name, &document.uri, workspace)?;
+            format!("```\n{}\n```", filters::usage(name, &filter.parameters))
+        }
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(document.range(range.start, range.end, workspace.encoding)),
+    })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcResponse<ResponseBody> {
-    id: JsonRpcRequestId,
-    result: ResponseBody,
-    #[serde(flatten)]
-    base: JsonRpcMessageBase,
+/// Name of the folder at `uri`, for clients that only send a root URI
+fn folder_name(uri: &Uri) -> String {
+    let path = uri.path().as_str().trim_end_matches('/');
+    path.rsplit('/').next().unwrap_or(path).to_owned()
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcNotification<NotificationBody> {
-    /// How do i enforce that NotificationBody must have serde deserializer implemented
-    /// such that it's a JSON object containing keys method: string and params: any
-    #[serde(flatten)]
-    notification: NotificationBody,
-    #[serde(flatten)]
-    base: JsonRpcMessageBase,
+/// `params` as JSON for traces
+fn json_or_none(params: &Option<serde_json::Value>) -> String {
+    params.as_ref().map_or_else(
+        || "No parameters provided.".to_owned(),
+        |params| params.to_string(),
+    )
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcRequest<RequestBody> {
-    id: JsonRpcRequestId,
-    /// How do i enforce that ReqeustBody must have serde deserializer implemented
-    /// such that it's a JSON object containing keys method: string and params: any
-    #[serde(flatten)]
-    request: RequestBody,
-    #[serde(flatten)]
-    base: JsonRpcMessageBase,
+/// The message a panic was started with, if it was given one
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcGenericRequestBody {
-    method: String,
-    params: Option<serde_json::Value>,
+struct LSHeader {
+    content_length: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum JsonRpcRequestId {
-    String(String),
-    Integer(i32),
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Output the messages sent by a server can be read back from
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Output {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn server() -> (LServer, Output) {
+        let output = Output::default();
+        (LServer::with_io(io::empty(), output.clone()), output)
+    }
+
+    #[test]
+    fn panics_become_internal_errors() {
+        let (mut server, _) = server();
+        let result: LSResult<()> = server.catch_panic("test/panic", |_| panic!("boom"));
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), -32603);
+        assert_eq!(err.message(), "Internal error: 'test/panic' panicked: boom");
+        // the server keeps handling messages
+        let result = server.catch_panic("test/ok", |_| Ok(1));
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    fn the_user_is_told_about_repeated_panics() {
+        let (mut server, output) = server();
+        for _ in 1..PANIC_REPORT_THRESHOLD {
+            let _ = server.catch_panic("test/panic", |_| -> LSResult<()> { panic!("boom") });
+        }
+        assert!(!output.text().contains(notification::ShowMessage::METHOD));
+        let _ = server.catch_panic("test/panic", |_| -> LSResult<()> { panic!("boom") });
+        assert!(output.text().contains(notification::ShowMessage::METHOD));
+        assert_eq!(server.panic_count, PANIC_REPORT_THRESHOLD);
+    }
+
+    #[test]
+    fn unknown_methods_are_not_found() {
+        let (mut server, _) = server();
+        let err = server.message_response("test/unknown", None).unwrap_err();
+        assert_eq!(err.code(), -32601);
+        assert_eq!(
+            err.data(),
+            Some(serde_json::json!({ "method": "test/unknown" }))
+        );
+    }
+
+    #[test]
+    fn invalid_params_are_reported() {
+        let (mut server, _) = server();
+        let err = server
+            .message_response(
+                request::HoverRequest::METHOD,
+                Some(serde_json::json!({ "position": "nowhere" })),
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), -32602);
+        assert_eq!(err.data(), None);
+    }
 }
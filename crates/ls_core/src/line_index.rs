@@ -0,0 +1,244 @@
+use lsp_types::{Position, PositionEncodingKind, Range};
+
+/// The unit `Position::character` is counted in.
+///
+/// Negotiated with the client during `initialize`. UTF-16 is what
+/// every client must support, so that's what we fall back to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Picks the first encoding from the client's preference list we
+    /// understand, or UTF-16 when the client didn't send any
+    pub(crate) fn negotiate(client_encodings: Option<&[PositionEncodingKind]>) -> Self {
+        client_encodings
+            .unwrap_or_default()
+            .iter()
+            .find_map(Self::from_kind)
+            .unwrap_or_default()
+    }
+
+    fn from_kind(kind: &PositionEncodingKind) -> Option<Self> {
+        if *kind == PositionEncodingKind::UTF8 {
+            Some(Self::Utf8)
+        } else if *kind == PositionEncodingKind::UTF16 {
+            Some(Self::Utf16)
+        } else if *kind == PositionEncodingKind::UTF32 {
+            Some(Self::Utf32)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn kind(&self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    fn char_len(&self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+/// Byte offsets of the start of each line of a text, used to convert
+/// between LSP positions and byte offsets in any [`PositionEncoding`].
+///
+/// `\n`, `\r\n` and `\r` are all treated as line terminators, as the spec asks
+#[derive(Debug, Clone)]
+pub(crate) struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let bytes = text.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                    line_starts.push(i + 2);
+                    i += 1;
+                }
+                b'\r' | b'\n' => line_starts.push(i + 1),
+                _ => {}
+            }
+            i += 1;
+        }
+        Self {
+            line_starts,
+            len: text.len(),
+        }
+    }
+
+    /// Byte offset of `position` in `text`.
+    ///
+    /// Positions past the end of a line resolve to the end of that line
+    /// and positions past the last line resolve to the end of the text
+    pub(crate) fn offset(
+        &self,
+        text: &str,
+        position: Position,
+        encoding: PositionEncoding,
+    ) -> usize {
+        let line = position.line as usize;
+        let Some(&line_start) = self.line_starts.get(line) else {
+            return self.len;
+        };
+        let line_end = self.line_end(text, line);
+        let mut remaining = position.character as usize;
+        for (i, c) in text[line_start..line_end].char_indices() {
+            let char_len = encoding.char_len(c);
+            // a position can't point in the middle of a character,
+            // so one that does is moved to the character's start
+            if remaining < char_len {
+                return line_start + i;
+            }
+            remaining -= char_len;
+        }
+        line_end
+    }
+
+    /// LSP position of the byte `offset` in `text`
+    pub(crate) fn position(
+        &self,
+        text: &str,
+        offset: usize,
+        encoding: PositionEncoding,
+    ) -> Position {
+        let offset = offset.min(self.len);
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let line_start = self.line_starts[line];
+        let character = text[line_start..offset]
+            .chars()
+            .map(|c| encoding.char_len(c))
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(crate) fn range(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+        encoding: PositionEncoding,
+    ) -> Range {
+        Range::new(
+            self.position(text, start, encoding),
+            self.position(text, end, encoding),
+        )
+    }
+
+    /// Byte offset where the content of `line` ends, excluding the terminator
+    fn line_end(&self, text: &str, line: usize) -> usize {
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+        let content = &text[self.line_starts[line]..end];
+        let content = content
+            .strip_suffix("\r\n")
+            .or_else(|| content.strip_suffix('\n'))
+            .or_else(|| content.strip_suffix('\r'))
+            .unwrap_or(content);
+        self.line_starts[line] + content.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One character each of 1, 2 and 4 bytes between ASCII
+    const TEXT: &str = "aé😀b\nc";
+
+    fn offset(text: &str, line: u32, character: u32, encoding: PositionEncoding) -> usize {
+        LineIndex::new(text).offset(text, Position::new(line, character), encoding)
+    }
+
+    fn position(text: &str, offset: usize, encoding: PositionEncoding) -> Position {
+        LineIndex::new(text).position(text, offset, encoding)
+    }
+
+    #[test]
+    fn characters_are_counted_in_the_encoding() {
+        // `b` is at byte 7
+        for (encoding, character) in [
+            (PositionEncoding::Utf8, 7),
+            (PositionEncoding::Utf16, 4),
+            (PositionEncoding::Utf32, 3),
+        ] {
+            assert_eq!(offset(TEXT, 0, character, encoding), 7, "{encoding:?}");
+            assert_eq!(
+                position(TEXT, 7, encoding),
+                Position::new(0, character),
+                "{encoding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn positions_inside_a_character_move_to_its_start() {
+        // the second UTF-16 code unit of `😀`, and its third byte
+        assert_eq!(offset(TEXT, 0, 3, PositionEncoding::Utf16), 3);
+        assert_eq!(offset(TEXT, 0, 5, PositionEncoding::Utf8), 3);
+    }
+
+    #[test]
+    fn every_line_terminator_ends_a_line() {
+        let text = "one\r\ntwo\rthree\nfour";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_starts, [0, 5, 9, 15]);
+        for (offset, expected) in [(5, (1, 0)), (9, (2, 0)), (17, (3, 2))] {
+            assert_eq!(
+                index.position(text, offset, PositionEncoding::Utf16),
+                Position::new(expected.0, expected.1)
+            );
+        }
+    }
+
+    #[test]
+    fn positions_past_the_end_of_a_line() {
+        let text = "one\r\ntwo";
+        // the end of the line is before its terminator
+        assert_eq!(offset(text, 0, 3, PositionEncoding::Utf16), 3);
+        assert_eq!(offset(text, 0, 40, PositionEncoding::Utf16), 3);
+        assert_eq!(offset(text, 1, 40, PositionEncoding::Utf16), 8);
+        assert_eq!(offset(text, 7, 0, PositionEncoding::Utf16), 8);
+        assert_eq!(
+            position(text, 100, PositionEncoding::Utf16),
+            Position::new(1, 3)
+        );
+    }
+
+    #[test]
+    fn negotiation_picks_the_first_known_encoding() {
+        let kinds = [
+            PositionEncodingKind::new("utf-7"),
+            PositionEncodingKind::UTF32,
+            PositionEncodingKind::UTF8,
+        ];
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&kinds)),
+            PositionEncoding::Utf32
+        );
+        assert_eq!(PositionEncoding::negotiate(None), PositionEncoding::Utf16);
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&kinds[..1])),
+            PositionEncoding::Utf16
+        );
+    }
+}
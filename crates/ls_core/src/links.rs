@@ -0,0 +1,157 @@
+//! The `templateUrl`s of scripts and the templates they name, for going
+//! from a controller or component to its template and back
+
+use std::ops::Range;
+
+use crate::{
+    Workspace,
+    controller::find_controller,
+    document::Document,
+    navigation::templates_of,
+    registry::{parse_script, property, visit},
+    uri::DocumentUri,
+};
+
+/// The string of a `templateUrl: 'app/foo/foo.html'` in a script
+#[derive(Debug, Clone)]
+pub(crate) struct TemplateUrl {
+    /// The URL as written
+    pub(crate) url: String,
+    /// Range of the URL, inside the quotes
+    pub(crate) range: Range<usize>,
+}
+
+/// The `templateUrl`s of the script `document`, in the order they're written
+pub(crate) fn template_urls(document: &Document) -> Vec<TemplateUrl> {
+    let Some((_, tree)) = parse_script(document) else {
+        return vec![];
+    };
+    let text = document.text();
+    let mut urls = vec![];
+    visit(tree.root_node(), &mut |node| {
+        let Some(url) = property(node, "templateUrl", text)
+            .filter(|url| url.kind() == "string")
+            .and_then(|url| url.named_child(0))
+        else {
+            return;
+        };
+        urls.push(TemplateUrl {
+            url: text[url.byte_range()].to_owned(),
+            range: url.byte_range(),
+        });
+    });
+    urls
+}
+
+/// The `templateUrl` at `offset` of the script `document`
+pub(crate) fn template_url_at(document: &Document, offset: usize) -> Option<TemplateUrl> {
+    template_urls(document)
+        .into_iter()
+        .find(|url| url.range.start <= offset && offset <= url.range.end)
+}
+
+/// The template `url`, a `templateUrl` of the script `script`, names.
+///
+/// URLs starting with `./` or `../` are relative to the script. Others are
+/// looked for under each of the `template_base_paths` of the root in turn,
+/// and then among the templates of the workspace whose path ends with them
+pub(crate) fn resolve_template_url(
+    url: &str,
+    script: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<DocumentUri> {
+    let exists = |uri: &DocumentUri| workspace.documents.get_or_read(uri).is_some();
+    if is_relative(url) {
+        let (directory, _) = script.path().rsplit_once('/')?;
+        let template = script.with_path(normalize(&format!("{directory}/{url}")));
+        return exists(&template).then_some(template);
+    }
+    let url = url.trim_start_matches('/');
+    if url.is_empty() {
+        return None;
+    }
+    if let Some(root) = workspace.root() {
+        for base in &workspace.config.template_base_paths {
+            let path = std::path::absolute(root.join(base).join(url)).ok()?;
+            let template = DocumentUri::from_file_path(&path);
+            let template = template.with_path(normalize(template.path()));
+            if exists(&template) {
+                return Some(template);
+            }
+        }
+    }
+    workspace
+        .templates()
+        .into_iter()
+        .find(|template| ends_with(template, url))
+}
+
+/// Whether `url`, a `templateUrl` of the script `script`, is the template
+/// at `template`. Unlike [`resolve_template_url`], URLs that aren't relative
+/// are only matched against the end of the template's path, so that it's
+/// cheap enough to ask of every template
+pub(crate) fn refers_to(url: &str, script: &DocumentUri, template: &DocumentUri) -> bool {
+    if is_relative(url) {
+        let Some((directory, _)) = script.path().rsplit_once('/') else {
+            return false;
+        };
+        return normalize(&format!("{directory}/{url}")) == normalize(template.path());
+    }
+    let url = url.trim_start_matches('/');
+    !url.is_empty() && ends_with(template, url)
+}
+
+/// The template of the script at `uri`: the first of its `templateUrl`s that
+/// resolves, or else a template bound to it by its file name or a route
+pub(crate) fn find_template(uri: &DocumentUri, workspace: &Workspace) -> Option<Document> {
+    let script = workspace.documents.get_or_read(uri)?;
+    template_urls(&script)
+        .iter()
+        .find_map(|url| resolve_template_url(&url.url, uri, workspace))
+        .and_then(|template| workspace.documents.get_or_read(&template))
+        .or_else(|| templates_of(uri, workspace).into_iter().next())
+}
+
+/// The controller of the template at `uri`: the one it's bound to, or else
+/// the first script with a `templateUrl` naming it
+pub(crate) fn find_template_controller(
+    uri: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<Document> {
+    find_controller(uri, workspace).or_else(|| {
+        workspace.scripts(uri).into_iter().find(|script| {
+            workspace
+                .index(script)
+                .template_urls
+                .iter()
+                .any(|url| refers_to(&url.url, &script.uri, uri))
+        })
+    })
+}
+
+fn is_relative(url: &str) -> bool {
+    url.starts_with("./") || url.starts_with("../")
+}
+
+/// Whether the path of `template` ends with the segments of `url`
+fn ends_with(template: &DocumentUri, url: &str) -> bool {
+    template
+        .path()
+        .strip_suffix(url)
+        .is_some_and(|rest| rest.ends_with('/'))
+}
+
+/// `path` without its `.` and `..` segments
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
@@ -0,0 +1,448 @@
+//! Going from a symbol to where it's defined or used, shared by the request
+//! handlers and the command line
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{Position, PositionEncodingKind};
+
+use crate::{
+    Workspace,
+    config::{Config, project_root},
+    controller::{
+        Symbol, camel_case, controller_members, find_controller, named_controller, pascal_case,
+        template_binding,
+    },
+    document::Document,
+    embedded::{embedded_templates, template_at},
+    line_index::PositionEncoding,
+    links::{refers_to, resolve_template_url, template_url_at},
+    registry::{Registration, RegistrationKind, Usage},
+    template::{ControllerReference, Template},
+    uri::DocumentUri,
+};
+
+/// A range in a file on disk, with `character` counted in unicode code points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    pub path: PathBuf,
+    pub range: lsp_types::Range,
+}
+
+/// Where the symbol at `position` of the template at `path` is defined
+pub fn definition_at(
+    path: &Path,
+    position: Position,
+    config: Config,
+) -> io::Result<Vec<FileLocation>> {
+    let workspace = workspace(path, config)?;
+    let document = read(path)?;
+    let offset = document.offset(position, workspace.encoding);
+    Ok(find_definition(&document, offset, &workspace)
+        .into_iter()
+        .filter_map(|(document, range)| file_location(&document, range))
+        .collect())
+}
+
+/// Where the controller member at `position` of the file at `path` is used
+/// in templates, its declaration first when `include_declaration` is set
+pub fn references_at(
+    path: &Path,
+    position: Position,
+    include_declaration: bool,
+    config: Config,
+) -> io::Result<Vec<FileLocation>> {
+    let workspace = workspace(path, config)?;
+    let document = read(path)?;
+    let offset = document.offset(position, workspace.encoding);
+    Ok(
+        find_references(&document, offset, include_declaration, &workspace)
+            .into_iter()
+            .filter_map(|(document, range)| file_location(&document, range))
+            .collect(),
+    )
+}
+
+/// A workspace for the project `path` is in, see [`project_root`]
+fn workspace(path: &Path, config: Config) -> io::Result<Workspace> {
+    let mut workspace = Workspace::with_root(project_root(path)?)
+        .with_position_encoding(PositionEncodingKind::UTF32);
+    workspace.set_config(config).map_err(io::Error::other)?;
+    Ok(workspace)
+}
+
+fn read(path: &Path) -> io::Result<Document> {
+    let text = fs::read_to_string(path)?;
+    let uri = DocumentUri::from_file_path(&std::path::absolute(path)?);
+    Ok(Document::new(uri, text, None))
+}
+
+fn file_location(document: &Document, range: Range<usize>) -> Option<FileLocation> {
+    Some(FileLocation {
+        path: document.uri.to_file_path()?,
+        range: document.range(range.start, range.end, PositionEncoding::Utf32),
+    })
+}
+
+/// The declaration of the controller member referenced at `offset` of the
+/// template `document`, or of a template embedded in the script `document`.
+/// In a script, a `templateUrl` at `offset` goes to the template it names
+pub(crate) fn find_definition(
+    document: &Document,
+    offset: usize,
+    workspace: &Workspace,
+) -> Option<(Document, Range<usize>)> {
+    if workspace.is_script(&document.uri)
+        && let Some(url) = template_url_at(document, offset)
+    {
+        let template = resolve_template_url(&url.url, &document.uri, workspace)?;
+        return Some((workspace.documents.get_or_read(&template)?, 0..0));
+    }
+    let document = &template_at(document, offset, workspace)?;
+    let template = Template::parse(document, workspace);
+    if let Some(tag) = template.tag_at(offset) {
+        return find_component(&tag.name, &document.uri, workspace);
+    }
+    if let Some(attribute) = template.attribute_at(offset) {
+        if let Some((script, component)) = find_registration_of(
+            &attribute.tag_name,
+            Usage::Element,
+            &document.uri,
+            workspace,
+        ) && let Some(binding) = component
+            .bindings
+            .iter()
+            .find(|binding| binding.attribute == camel_case(&attribute.directive_name()))
+        {
+            return Some((script, binding.range.clone()));
+        }
+        return find_registration(
+            &attribute.directive_name(),
+            Usage::Attribute,
+            &document.uri,
+            workspace,
+        );
+    }
+    if let Some(scope) = template.scope_name_at(offset) {
+        return find_named_controller(&scope.name, &document.uri, workspace);
+    }
+    if let Some(class) = template.class_at(document.text(), offset) {
+        return find_registration(class, Usage::Class, &document.uri, workspace);
+    }
+    if let Some(filter) = template.filter_at(document.text(), offset) {
+        return find_filter(&document.text()[filter], &document.uri, workspace)
+            .map(|(script, filter)| (script, filter.range));
+    }
+    let reference = find_controller_reference(document, offset, workspace)?;
+    let (controller, member) = resolve_member(document, &reference, workspace)?;
+    Some((controller, member.range))
+}
+
+/// The `vm.member` at `offset` of the template `document`
+pub(crate) fn find_controller_reference(
+    document: &Document,
+    offset: usize,
+    workspace: &Workspace,
+) -> Option<ControllerReference> {
+    let aliases = template_binding(document, workspace).aliases;
+    Template::parse(document, workspace)
+        .controller_references(document, &aliases)
+        .into_iter()
+        .find(|reference| {
+            reference.expression_range.contains(&offset) || reference.range.end == offset
+        })
+}
+
+/// The controller of the template `document` and the member `reference` is to
+pub(crate) fn resolve_member(
+    document: &Document,
+    reference: &ControllerReference,
+    workspace: &Workspace,
+) -> Option<(Document, Symbol)> {
+    let member_of = |controller: Document| {
+        let member = controller_members(&controller)
+            .into_iter()
+            .find(|member| member.name == reference.name)?;
+        Some((controller, member))
+    };
+    if let Some(name) = &reference.controller {
+        return member_of(named_controller(name, &document.uri, workspace)?);
+    }
+    let binding = template_binding(document, workspace);
+    binding.controller.and_then(member_of).or_else(|| {
+        binding
+            .given
+            .into_iter()
+            .find(|(_, key)| key.name == reference.name)
+    })
+}
+
+/// The `vm.member` references in templates to the member declared at `offset`
+/// of the controller `document`
+pub(crate) fn find_references(
+    document: &Document,
+    offset: usize,
+    include_declaration: bool,
+    workspace: &Workspace,
+) -> Vec<(Document, Range<usize>)> {
+    let Some(member) = controller_members(document)
+        .into_iter()
+        .find(|member| member.range.contains(&offset) || member.range.end == offset)
+    else {
+        return vec![];
+    };
+    let mut references = vec![];
+    if include_declaration {
+        references.push((document.clone(), member.range.clone()));
+    }
+    references.extend(find_template_references(
+        &document.uri,
+        &member.name,
+        workspace,
+    ));
+    references
+}
+
+/// Where the component or element directive used as the element `tag_name`
+/// is registered, or else the component's controller class: `<user-card>` is
+/// the component `userCard` and its class is e.g. `UserCardController`.
+/// Scripts are looked for near `near`
+pub(crate) fn find_component(
+    tag_name: &str,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<(Document, Range<usize>)> {
+    find_registration(tag_name, Usage::Element, near, workspace).or_else(|| {
+        let scripts = workspace.scripts(near);
+        let class = pascal_case(tag_name);
+        workspace
+            .config
+            .controller_suffixes
+            .iter()
+            .find_map(|suffix| {
+                let class = format!("{class}{suffix}");
+                scripts.iter().find_map(|script| {
+                    let range = workspace.index(script).find_class(&class)?;
+                    Some((script.clone(), range))
+                })
+            })
+    })
+}
+
+/// Where the component or directive that applies to `name` used as `usage`
+/// is registered. `name` is normalized like an HTML name, so `my-tooltip` is
+/// the directive `myTooltip`
+pub(crate) fn find_registration(
+    name: &str,
+    usage: Usage,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<(Document, Range<usize>)> {
+    find_registration_of(name, usage, near, workspace)
+        .map(|(script, registration)| (script, registration.range))
+}
+
+/// Like [`find_registration`], with the whole registration
+pub(crate) fn find_registration_of(
+    name: &str,
+    usage: Usage,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<(Document, Registration)> {
+    let name = camel_case(name);
+    workspace.scripts(near).into_iter().find_map(|script| {
+        let registration = workspace
+            .index(&script)
+            .registrations
+            .iter()
+            .find(|registration| registration.name == name && registration.allows(usage))?
+            .clone();
+        Some((script, registration))
+    })
+}
+
+/// Where the controller `name`, as `ng-controller` names it, is registered
+/// with `.controller('name', ...)`, or else where its class is declared
+pub(crate) fn find_named_controller(
+    name: &str,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<(Document, Range<usize>)> {
+    let scripts = workspace.scripts(near);
+    scripts
+        .iter()
+        .find_map(|script| {
+            workspace
+                .index(script)
+                .registrations
+                .iter()
+                .find(|registration| {
+                    registration.kind == RegistrationKind::Controller && registration.name == name
+                })
+                .map(|registration| (script.clone(), registration.range.clone()))
+        })
+        .or_else(|| {
+            scripts.iter().find_map(|script| {
+                let range = workspace.index(script).find_class(name)?;
+                Some((script.clone(), range))
+            })
+        })
+}
+
+/// The registration of the filter `name` in the scripts near `near`
+pub(crate) fn find_filter(
+    name: &str,
+    near: &DocumentUri,
+    workspace: &Workspace,
+) -> Option<(Document, Registration)> {
+    workspace.scripts(near).into_iter().find_map(|script| {
+        let filter = workspace
+            .index(&script)
+            .registrations
+            .iter()
+            .find(|registration| {
+                registration.kind == RegistrationKind::Filter && registration.name == name
+            })?
+            .clone();
+        Some((script, filter))
+    })
+}
+
+/// The `vm.name` references to the controller at `controller`: in the
+/// templates bound to it, those embedded in scripts included, and inside the
+/// `ng-controller`s naming it in any template of the workspace
+pub(crate) fn find_template_references(
+    controller: &DocumentUri,
+    name: &str,
+    workspace: &Workspace,
+) -> Vec<(Document, Range<usize>)> {
+    let bound = templates_of(controller, workspace);
+    let mut seen: HashSet<DocumentUri> =
+        bound.iter().map(|template| template.uri.clone()).collect();
+    let others = workspace
+        .templates()
+        .into_iter()
+        .filter(|uri| seen.insert(uri.clone()))
+        .filter_map(|uri| workspace.documents.get_or_read(&uri));
+    let embedded = workspace
+        .scripts(controller)
+        .into_iter()
+        .flat_map(|script| embedded_templates(&script))
+        .map(|embedded| embedded.document);
+    // whether each controller named by an `ng-controller` is this one
+    let mut named: HashMap<String, bool> = HashMap::new();
+    let mut references = vec![];
+    for (template, is_bound) in bound
+        .into_iter()
+        .map(|template| (template, true))
+        .chain(others.map(|template| (template, false)))
+        .chain(embedded.map(|template| (template, false)))
+    {
+        let parsed = Template::parse(&template, workspace);
+        let is_embedded = workspace.is_script(&template.uri);
+        if !is_bound && !is_embedded && parsed.scopes.is_empty() {
+            continue;
+        }
+        let binding = template_binding(&template, workspace);
+        // an embedded template is bound to the controller of its definition
+        let is_bound = is_bound
+            || is_embedded
+                && binding
+                    .controller
+                    .as_ref()
+                    .is_some_and(|bound| bound.uri == *controller);
+        let aliases = binding.aliases;
+        for reference in parsed.controller_references(&template, &aliases) {
+            if reference.name != name {
+                continue;
+            }
+            let is_controller = match &reference.controller {
+                Some(controller_name) => {
+                    *named.entry(controller_name.clone()).or_insert_with(|| {
+                        named_controller(controller_name, &template.uri, workspace)
+                            .is_some_and(|named| named.uri == *controller)
+                    })
+                }
+                None => is_bound,
+            };
+            if is_controller {
+                references.push((template.clone(), reference.range));
+            }
+        }
+    }
+    references
+}
+
+/// Templates bound to the controller at `uri`. Those sit in the same
+/// directory, open in the client or on disk, or are shown by a route
+pub(crate) fn templates_of(uri: &DocumentUri, workspace: &Workspace) -> Vec<Document> {
+    let directory = |uri: &DocumentUri| {
+        uri.path()
+            .rsplit_once('/')
+            .map(|(directory, _)| directory.to_owned())
+    };
+    let controller_directory = directory(uri);
+    let mut candidates: Vec<DocumentUri> = workspace
+        .documents
+        .iter()
+        .map(|document| document.uri.clone())
+        .filter(|candidate| directory(candidate) == controller_directory)
+        .collect();
+    if let Some(entries) = uri
+        .to_file_path()
+        .and_then(|path| fs::read_dir(path.parent()?).ok())
+    {
+        candidates.extend(entries.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            Some(DocumentUri::from_file_path(
+                &std::path::absolute(path).ok()?,
+            ))
+        }));
+    }
+    // and templates shown by a route with this controller, anywhere
+    let scripts = workspace.scripts(uri);
+    let mut controllers: HashMap<String, bool> = HashMap::new();
+    let mut is_this_controller = |name: &str| {
+        *controllers.entry(name.to_owned()).or_insert_with(|| {
+            named_controller(name, uri, workspace).is_some_and(|controller| controller.uri == *uri)
+        })
+    };
+    let routes: Vec<_> = scripts
+        .iter()
+        .flat_map(|script| {
+            workspace
+                .index(script)
+                .routes
+                .iter()
+                .map(|route| (script.uri.clone(), route.clone()))
+                .collect::<Vec<_>>()
+        })
+        .filter(|(_, route)| {
+            route
+                .controller
+                .as_deref()
+                .is_some_and(&mut is_this_controller)
+        })
+        .collect();
+    if !routes.is_empty() {
+        candidates.extend(workspace.templates().into_iter().filter(|template| {
+            routes
+                .iter()
+                .any(|(script, route)| refers_to(&route.template_url, script, template))
+        }));
+    }
+    let mut seen = HashSet::new();
+    candidates.sort_by_key(|candidate| candidate.path().to_owned());
+    candidates
+        .into_iter()
+        .filter(|candidate| workspace.is_template(candidate) && seen.insert(candidate.clone()))
+        .filter(|candidate| {
+            find_controller(candidate, workspace).is_some_and(|controller| controller.uri == *uri)
+        })
+        .filter_map(|candidate| workspace.documents.get_or_read(&candidate))
+        .collect()
+}
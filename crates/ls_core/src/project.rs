@@ -0,0 +1,148 @@
+//! The workspace folders the client has open, each analysed as its own project
+//! so that apps of a monorepo don't see each other's templates and controllers
+
+use std::path::Path;
+
+use lsp_types::{Uri, WorkspaceFolder};
+
+use crate::{Workspace, line_index::PositionEncoding, uri::DocumentUri};
+
+/// One workspace folder and what's known about it
+#[derive(Debug)]
+pub(crate) struct Project {
+    /// `None` for the project of documents outside every folder
+    pub(crate) folder: Option<WorkspaceFolder>,
+    pub(crate) workspace: Workspace,
+    /// The `myls` section of the client's settings for this folder
+    pub(crate) client_settings: serde_json::Value,
+}
+
+impl Project {
+    fn new(folder: Option<WorkspaceFolder>, encoding: PositionEncoding) -> Self {
+        let root = folder
+            .as_ref()
+            .and_then(|folder| DocumentUri::try_from(&folder.uri).ok())
+            .and_then(|uri| uri.to_file_path());
+        let mut workspace = match root {
+            Some(root) => Workspace::with_root(root),
+            None => Workspace::new(),
+        };
+        workspace.encoding = encoding;
+        Self {
+            folder,
+            workspace,
+            client_settings: serde_json::Value::Null,
+        }
+    }
+
+    pub(crate) fn root(&self) -> Option<&Path> {
+        self.workspace.root()
+    }
+
+    /// Uri of the folder, as settings are scoped with
+    pub(crate) fn scope(&self) -> Option<&Uri> {
+        self.folder.as_ref().map(|folder| &folder.uri)
+    }
+}
+
+/// The projects of every workspace folder, plus one for the documents that
+/// are in none of them
+#[derive(Debug)]
+pub(crate) struct Projects {
+    /// The project for documents outside every folder comes first
+    projects: Vec<Project>,
+    encoding: PositionEncoding,
+}
+
+impl Default for Projects {
+    fn default() -> Self {
+        Self {
+            projects: vec![Project::new(None, PositionEncoding::default())],
+            encoding: PositionEncoding::default(),
+        }
+    }
+}
+
+impl Projects {
+    pub(crate) fn encoding(&self) -> PositionEncoding {
+        self.encoding
+    }
+
+    pub(crate) fn set_encoding(&mut self, encoding: PositionEncoding) {
+        self.encoding = encoding;
+        for project in &mut self.projects {
+            project.workspace.encoding = encoding;
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Project> {
+        self.projects.iter()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Project> {
+        self.projects.iter_mut()
+    }
+
+    /// Adds the projects of `added` and drops those of `removed`, moving the
+    /// open documents over to the projects that now own them
+    pub(crate) fn change_folders(
+        &mut self,
+        added: Vec<WorkspaceFolder>,
+        removed: &[WorkspaceFolder],
+    ) {
+        let mut documents = vec![];
+        for project in &mut self.projects {
+            documents.extend(project.workspace.documents.take_all());
+        }
+        self.projects.retain(|project| {
+            !removed
+                .iter()
+                .any(|folder| Some(&folder.uri) == project.scope())
+        });
+        for folder in added {
+            if self
+                .projects
+                .iter()
+                .any(|project| project.scope() == Some(&folder.uri))
+            {
+                continue;
+            }
+            let project = Project::new(Some(folder), self.encoding);
+            tracing::debug!("New project at {:?}", project.root());
+            self.projects.push(project);
+        }
+        for document in documents {
+            self.owner_mut(&document.uri)
+                .workspace
+                .documents
+                .insert(document);
+        }
+    }
+
+    /// The project the document at `uri` belongs to: that of the innermost
+    /// folder it's in, or the one for documents outside every folder
+    pub(crate) fn owner(&self, uri: &DocumentUri) -> &Project {
+        &self.projects[self.owner_index(uri)]
+    }
+
+    pub(crate) fn owner_mut(&mut self, uri: &DocumentUri) -> &mut Project {
+        let index = self.owner_index(uri);
+        &mut self.projects[index]
+    }
+
+    fn owner_index(&self, uri: &DocumentUri) -> usize {
+        let Some(path) = uri.to_file_path() else {
+            return 0;
+        };
+        self.projects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, project)| {
+                let root = project.root()?;
+                path.starts_with(root)
+                    .then_some((index, root.components().count()))
+            })
+            .max_by_key(|&(_, depth)| depth)
+            .map_or(0, |(index, _)| index)
+    }
+}
@@ -0,0 +1,33 @@
+//! Typed model of the LSP 3.17 messages and capabilities.
+//!
+//! Re-exports [`lsp_types`] so that handlers and users of `ls_core` get
+//! the same types without having to depend on it directly, along with the
+//! requests myls adds to the protocol.
+
+pub use lsp_types::*;
+
+/// Requests that aren't part of LSP, understood by myls only
+pub mod myls {
+    use lsp_types::{Location, TextDocumentIdentifier, request::Request};
+
+    /// `myls/goToTemplate`: from a controller or component script to its
+    /// template
+    #[derive(Debug)]
+    pub enum GoToTemplate {}
+
+    impl Request for GoToTemplate {
+        type Params = TextDocumentIdentifier;
+        type Result = Option<Location>;
+        const METHOD: &'static str = "myls/goToTemplate";
+    }
+
+    /// `myls/goToController`: from a template to the script of its controller
+    #[derive(Debug)]
+    pub enum GoToController {}
+
+    impl Request for GoToController {
+        type Params = TextDocumentIdentifier;
+        type Result = Option<Location>;
+        const METHOD: &'static str = "myls/goToController";
+    }
+}
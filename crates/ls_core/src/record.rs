@@ -0,0 +1,85 @@
+//! Recording the messages of a session to replay it later, see [`crate::replay`]
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Which way a recorded message went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// From the client to the server
+    In,
+    /// From the server to the client
+    Out,
+}
+
+/// One line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Milliseconds since the unix epoch
+    pub timestamp: u128,
+    pub direction: Direction,
+    /// The message as it was sent. An inbound message that wasn't valid JSON
+    /// is kept as a string of its raw content
+    pub message: Value,
+}
+
+/// The messages of a session, read from a JSONL file written by the server
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub messages: Vec<RecordedMessage>,
+}
+
+impl Recording {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?).map_err(io::Error::other)
+    }
+
+    /// Parses a recording, one message per line. Blank lines are skipped
+    pub fn parse(recording: &str) -> serde_json::Result<Self> {
+        let messages = recording
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?;
+        Ok(Self { messages })
+    }
+
+    pub fn messages_in(&self, direction: Direction) -> impl Iterator<Item = &Value> {
+        self.messages
+            .iter()
+            .filter(move |message| message.direction == direction)
+            .map(|message| &message.message)
+    }
+}
+
+/// Writes each message going through the server as a line of JSON
+pub(crate) struct Recorder {
+    output: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+    pub(crate) fn new(output: Box<dyn Write + Send>) -> Self {
+        Self { output }
+    }
+
+    pub(crate) fn record(&mut self, direction: Direction, message: Value) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let line = serde_json::to_string(&RecordedMessage {
+            timestamp,
+            direction,
+            message,
+        })?;
+        writeln!(self.output, "{line}")?;
+        self.output.flush()
+    }
+}
@@ -0,0 +1,576 @@
+//! Components, directives, filters and controllers registered with AngularJS,
+//! as in
+//! `angular.module('app').component('userCard', { ... })`
+
+use std::{ops::Range, sync::OnceLock};
+
+use tracing::debug;
+use tree_sitter::{Language, Node, Query, QueryCursor, StreamingIterator};
+
+use crate::{
+    controller::{Symbol, camel_case},
+    document::Document,
+};
+
+/// Calls registering a component, a directive, a filter or a controller.
+/// Whether the first argument is a string is checked afterwards
+const QUERY_REGISTRATIONS: &str = r#"
+(call_expression
+    function: (member_expression
+        property: (property_identifier) @kind)
+    arguments: (arguments) @arguments
+    (#any-of? @kind "component" "directive" "filter" "controller"))
+"#;
+
+static REGISTRATIONS: ScriptQuery = ScriptQuery::new(QUERY_REGISTRATIONS);
+
+/// A query of scripts, compiled the first time it's needed for each of
+/// TypeScript and JavaScript
+pub(crate) struct ScriptQuery {
+    source: &'static str,
+    typescript: OnceLock<Option<Query>>,
+    javascript: OnceLock<Option<Query>>,
+}
+
+impl ScriptQuery {
+    pub(crate) const fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            typescript: OnceLock::new(),
+            javascript: OnceLock::new(),
+        }
+    }
+
+    /// The query for scripts in `language`, as [`parse_script`] gives it
+    pub(crate) fn get(&self, language: &Language) -> Option<&Query> {
+        let compiled = if *language == tree_sitter_javascript::LANGUAGE.into() {
+            &self.javascript
+        } else {
+            &self.typescript
+        };
+        compiled
+            .get_or_init(|| {
+                Query::new(language, self.source)
+                    .inspect_err(|err| debug!("Script query error: {err}"))
+                    .ok()
+            })
+            .as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RegistrationKind {
+    Component,
+    Directive,
+    Filter,
+    Controller,
+}
+
+/// A component, directive, filter or controller registration
+#[derive(Debug, Clone)]
+pub(crate) struct Registration {
+    pub(crate) kind: RegistrationKind,
+    /// Name it's registered with, e.g. `userCard`
+    pub(crate) name: String,
+    /// Range of the name, inside the quotes
+    pub(crate) range: Range<usize>,
+    /// `restrict` of a directive's definition object, `None` when it isn't set
+    pub(crate) restrict: Option<String>,
+    /// Inputs and outputs of a component
+    pub(crate) bindings: Vec<Binding>,
+    /// Parameters of the function a filter's factory returns, the input first
+    pub(crate) parameters: Vec<String>,
+    /// Name of the class or function implementing a controller, or the
+    /// controller of a component, when it's given by name
+    pub(crate) controller: Option<String>,
+}
+
+/// A binding of a component, `user: '<'` in its `bindings` or a field
+/// decorated with `@Input()`, `@Output()` or `@Attr()`
+#[derive(Debug, Clone)]
+pub(crate) struct Binding {
+    /// Name of the controller property the binding sets, `user` for
+    /// `user: '<userData'`
+    pub(crate) name: String,
+    /// Name of the attribute that sets the binding as in the component,
+    /// `userData` for `user-data`. It's `name` unless the binding has an alias
+    pub(crate) attribute: String,
+    /// `<`, `=`, `=*`, `@` or `&`, followed by `?` when the binding is optional
+    pub(crate) mode: String,
+    /// Range of the name where the binding is declared
+    pub(crate) range: Range<usize>,
+}
+
+impl Binding {
+    /// Whether the element has to set the binding. Output (`&`) bindings
+    /// never have to, AngularJS hands the component a no-op without them
+    pub(crate) fn is_required(&self) -> bool {
+        !self.mode.contains(['?', '&'])
+    }
+}
+
+/// Decorators of component fields, as used by ng-metadata, and the binding
+/// mode they stand for
+const BINDING_DECORATORS: &[(&str, &str)] = &[("Input", "<?"), ("Output", "&"), ("Attr", "@?")];
+
+/// How a directive can be used in a template, the letters of `restrict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Usage {
+    /// `<my-directive>`
+    Element,
+    /// `<div my-directive>`
+    Attribute,
+    /// `<div class="my-directive">`
+    Class,
+}
+
+impl Registration {
+    /// Whether the registration applies when its name is used as `usage`.
+    /// Components are elements only, directives are elements and attributes
+    /// unless `restrict` says otherwise
+    pub(crate) fn allows(&self, usage: Usage) -> bool {
+        let letter = match usage {
+            Usage::Element => 'E',
+            Usage::Attribute => 'A',
+            Usage::Class => 'C',
+        };
+        match self.kind {
+            RegistrationKind::Component => usage == Usage::Element,
+            RegistrationKind::Directive => {
+                self.restrict.as_deref().unwrap_or("EA").contains(letter)
+            }
+            RegistrationKind::Filter | RegistrationKind::Controller => false,
+        }
+    }
+}
+
+/// The components, directives, filters and controllers registered in the
+/// script `document`
+pub(crate) fn registrations(document: &Document) -> Vec<Registration> {
+    let Some((language, tree)) = parse_script(document) else {
+        return vec![];
+    };
+    let Some(query) = REGISTRATIONS.get(&language) else {
+        return vec![];
+    };
+    let text = document.text();
+    let kind_index = query.capture_index_for_name("kind");
+    let arguments_index = query.capture_index_for_name("arguments");
+    let mut registrations = vec![];
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), text.as_bytes());
+    while let Some(m) = matches.next() {
+        let capture = |index| {
+            m.captures
+                .iter()
+                .find(|capture| Some(capture.index) == index)
+                .map(|capture| capture.node)
+        };
+        let (Some(kind), Some(arguments)) = (capture(kind_index), capture(arguments_index)) else {
+            continue;
+        };
+        let kind = match &text[kind.byte_range()] {
+            "component" => RegistrationKind::Component,
+            "filter" => RegistrationKind::Filter,
+            "controller" => RegistrationKind::Controller,
+            _ => RegistrationKind::Directive,
+        };
+        let Some(name) = arguments
+            .named_child(0)
+            .filter(|name| name.kind() == "string")
+            .and_then(|name| name.named_child(0))
+            .filter(|name| name.kind() == "string_fragment")
+        else {
+            continue;
+        };
+        let definition = arguments.named_child(1);
+        let mut registration = Registration {
+            kind,
+            name: text[name.byte_range()].to_owned(),
+            range: name.byte_range(),
+            restrict: None,
+            bindings: vec![],
+            parameters: vec![],
+            controller: None,
+        };
+        if let Some(definition) = definition {
+            let root = tree.root_node();
+            match kind {
+                RegistrationKind::Component => {
+                    registration.bindings = bindings(definition, root, text);
+                    registration.controller = property(definition, "controller", text)
+                        .and_then(|controller| function_name(controller, text));
+                }
+                RegistrationKind::Directive => {
+                    registration.restrict = restrict(definition, root, text);
+                }
+                RegistrationKind::Filter => {
+                    registration.parameters = filter_parameters(definition, root, text);
+                }
+                RegistrationKind::Controller => {
+                    registration.controller = function_name(definition, text);
+                }
+            }
+        }
+        registrations.push(registration);
+    }
+    visit(tree.root_node(), &mut |node| {
+        if node.kind() == "class_declaration"
+            && let Some(registration) = decorated_component(node, text)
+        {
+            registrations.push(registration);
+        }
+    });
+    registrations
+}
+
+/// The `bindings` of the component options `options`, the second argument of
+/// `.component()`, which may also name an object declared in the same file
+fn bindings(options: Node, root: Node, text: &str) -> Vec<Binding> {
+    let options = if options.kind() == "identifier" {
+        match find_declaration(root, &text[options.byte_range()], text) {
+            Some(options) => options,
+            None => return vec![],
+        }
+    } else {
+        options
+    };
+    let Some(bindings) = property(options, "bindings", text) else {
+        return vec![];
+    };
+    let mut cursor = bindings.walk();
+    bindings
+        .named_children(&mut cursor)
+        .filter(|pair| pair.kind() == "pair")
+        .filter_map(|pair| {
+            let key = pair.child_by_field_name("key")?;
+            let value = pair.child_by_field_name("value")?;
+            let key_range = match key.kind() {
+                "string" => key.named_child(0)?.byte_range(),
+                _ => key.byte_range(),
+            };
+            let name = &text[key_range.clone()];
+            let (mode, alias) = binding_definition(string_value(value, text)?)?;
+            Some(Binding {
+                name: name.to_owned(),
+                attribute: alias.unwrap_or(name).to_owned(),
+                mode: mode.to_owned(),
+                range: key_range,
+            })
+        })
+        .collect()
+}
+
+/// The mode of the binding `definition` and the attribute it's aliased to if
+/// any: `<?userData` is the optional one-way binding of `user-data`. `None`
+/// when it isn't a binding AngularJS would accept
+fn binding_definition(definition: &str) -> Option<(&str, Option<&str>)> {
+    let definition = definition.trim();
+    let mut end = match definition.chars().next()? {
+        '<' | '@' | '&' => 1,
+        '=' if definition[1..].starts_with('*') => 2,
+        '=' => 1,
+        _ => return None,
+    };
+    if definition[end..].starts_with('?') {
+        end += 1;
+    }
+    let alias = definition[end..].trim();
+    if !alias
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$'))
+    {
+        return None;
+    }
+    Some((
+        &definition[..end],
+        Some(alias).filter(|alias| !alias.is_empty()),
+    ))
+}
+
+/// A class decorated with ng-metadata's `@Component({ selector: 'user-card' })`,
+/// with the bindings of its decorated fields
+fn decorated_component(class: Node, text: &str) -> Option<Registration> {
+    // decorators of an exported class are on the export statement
+    let selector = class
+        .parent()
+        .filter(|parent| parent.kind() == "export_statement")
+        .into_iter()
+        .chain([class])
+        .flat_map(|node| decorators(node, text))
+        .filter(|(name, _)| *name == "Component")
+        .find_map(|(_, arguments)| property(arguments?.named_child(0)?, "selector", text))?;
+    let selector = selector
+        .named_child(0)
+        .filter(|_| selector.kind() == "string")?;
+    let mut bindings = vec![];
+    let body = class.child_by_field_name("body")?;
+    let mut cursor = body.walk();
+    for field in body.named_children(&mut cursor) {
+        let Some(field_name) = field.child_by_field_name("name") else {
+            continue;
+        };
+        for (decorator, arguments) in decorators(field, text) {
+            let Some((_, mode)) = BINDING_DECORATORS
+                .iter()
+                .find(|(name, _)| *name == decorator)
+            else {
+                continue;
+            };
+            // `@Input('alias')` binds the attribute `alias`
+            let alias = arguments
+                .and_then(|arguments| arguments.named_child(0))
+                .filter(|alias| alias.kind() == "string")
+                .and_then(|alias| alias.named_child(0));
+            let attribute = alias.unwrap_or(field_name);
+            bindings.push(Binding {
+                name: text[field_name.byte_range()].to_owned(),
+                attribute: text[attribute.byte_range()].to_owned(),
+                mode: (*mode).to_owned(),
+                range: attribute.byte_range(),
+            });
+        }
+    }
+    Some(Registration {
+        kind: RegistrationKind::Component,
+        name: camel_case(&text[selector.byte_range()]),
+        range: selector.byte_range(),
+        restrict: None,
+        bindings,
+        parameters: vec![],
+        controller: None,
+    })
+}
+
+/// The decorators on `node` by name, with the arguments they're called with
+fn decorators<'tree>(
+    node: Node<'tree>,
+    text: &'tree str,
+) -> impl Iterator<Item = (&'tree str, Option<Node<'tree>>)> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| child.kind() == "decorator")
+        .filter_map(|decorator| {
+            let expression = decorator.named_child(0)?;
+            match expression.kind() {
+                "call_expression" => {
+                    let function = expression.child_by_field_name("function")?;
+                    Some((
+                        &text[function.byte_range()],
+                        expression.child_by_field_name("arguments"),
+                    ))
+                }
+                "identifier" => Some((&text[expression.byte_range()], None)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// The value of the property `key` of the object `object`
+pub(crate) fn property<'tree>(object: Node<'tree>, key: &str, text: &str) -> Option<Node<'tree>> {
+    if object.kind() != "object" {
+        return None;
+    }
+    let mut cursor = object.walk();
+    object
+        .named_children(&mut cursor)
+        .filter(|pair| pair.kind() == "pair")
+        .find(|pair| {
+            pair.child_by_field_name("key")
+                .is_some_and(|name| text[name.byte_range()].trim_matches(['\'', '"']) == key)
+        })
+        .and_then(|pair| pair.child_by_field_name("value"))
+}
+
+/// The contents of the string literal `node`
+pub(crate) fn string_value<'a>(node: Node, text: &'a str) -> Option<&'a str> {
+    matches!(node.kind(), "string" | "template_string")
+        .then(|| text[node.byte_range()].trim_matches(['\'', '"', '`']))
+}
+
+/// `restrict` of the directive defined by `definition`, the second argument
+/// of `.directive()`. That's the first `restrict` property in it, or in the
+/// function it names when it's declared in the same file. With the inline
+/// array annotation, `['$timeout', factory]`, the factory is the last item
+fn restrict(definition: Node, root: Node, text: &str) -> Option<String> {
+    let definition = factory(definition, root, text)?;
+    let mut restrict = None;
+    visit(definition, &mut |node| {
+        if restrict.is_none()
+            && node.kind() == "pair"
+            && let Some(key) = node.child_by_field_name("key")
+            && text[key.byte_range()].trim_matches(['\'', '"']) == "restrict"
+            && let Some(value) = node.child_by_field_name("value")
+            && value.kind() == "string"
+        {
+            restrict = Some(
+                text[value.byte_range()]
+                    .trim_matches(['\'', '"', '`'])
+                    .to_owned(),
+            );
+        }
+    });
+    restrict
+}
+
+/// Name of the function `definition` stands for when it's given by name,
+/// possibly as the last item of an inline array annotation
+pub(crate) fn function_name(definition: Node, text: &str) -> Option<String> {
+    let definition = match definition.kind() {
+        "array" => definition.named_child(definition.named_child_count().checked_sub(1)?)?,
+        _ => definition,
+    };
+    (definition.kind() == "identifier").then(|| text[definition.byte_range()].to_owned())
+}
+
+/// The factory function `definition` stands for: itself, the last item of an
+/// inline array annotation, or the declaration of the name it is
+fn factory<'tree>(definition: Node<'tree>, root: Node<'tree>, text: &str) -> Option<Node<'tree>> {
+    let definition = match definition.kind() {
+        "array" => definition.named_child(definition.named_child_count().checked_sub(1)?)?,
+        _ => definition,
+    };
+    if definition.kind() == "identifier" {
+        find_declaration(root, &text[definition.byte_range()], text)
+    } else {
+        Some(definition)
+    }
+}
+
+/// Parameters of the filter function the factory `definition`, the second
+/// argument of `.filter()`, returns: `function () { return function (input,
+/// currency) { ... } }` has `input` and `currency`
+fn filter_parameters(definition: Node, root: Node, text: &str) -> Vec<String> {
+    let is_function = |node: &Node| {
+        matches!(
+            node.kind(),
+            "function_expression" | "function" | "arrow_function" | "function_declaration"
+        )
+    };
+    let Some(factory) = factory(definition, root, text).filter(is_function) else {
+        return vec![];
+    };
+    let Some(body) = factory.child_by_field_name("body") else {
+        return vec![];
+    };
+    // `() => (input) => ...` or the first function returned from the body
+    let filter = if is_function(&body) {
+        Some(body)
+    } else {
+        let mut cursor = body.walk();
+        body.named_children(&mut cursor)
+            .filter(|statement| statement.kind() == "return_statement")
+            .find_map(|statement| statement.named_child(0))
+            .and_then(|returned| declared_function(returned, factory, root, text))
+            .filter(is_function)
+    };
+    let Some(parameters) = filter.and_then(|filter| {
+        filter
+            .child_by_field_name("parameters")
+            .or_else(|| filter.child_by_field_name("parameter"))
+    }) else {
+        return vec![];
+    };
+    if parameters.kind() == "identifier" {
+        // `input => ...`
+        return vec![text[parameters.byte_range()].to_owned()];
+    }
+    let mut cursor = parameters.walk();
+    parameters
+        .named_children(&mut cursor)
+        .filter_map(|parameter| {
+            let name = match parameter.kind() {
+                "required_parameter" | "optional_parameter" => {
+                    parameter.child_by_field_name("pattern")?
+                }
+                "assignment_pattern" => parameter.child_by_field_name("left")?,
+                "identifier" => parameter,
+                _ => return None,
+            };
+            Some(text[name.byte_range()].to_owned())
+        })
+        .collect()
+}
+
+/// `returned` itself, or the function it names when it's declared in
+/// `factory` or at the top of the file
+fn declared_function<'tree>(
+    returned: Node<'tree>,
+    factory: Node<'tree>,
+    root: Node<'tree>,
+    text: &str,
+) -> Option<Node<'tree>> {
+    if returned.kind() != "identifier" {
+        return Some(returned);
+    }
+    let name = &text[returned.byte_range()];
+    find_declaration(factory, name, text).or_else(|| find_declaration(root, name, text))
+}
+
+/// The function declared as `name`, or the value of the variable `name`
+fn find_declaration<'tree>(root: Node<'tree>, name: &str, text: &str) -> Option<Node<'tree>> {
+    let mut found = None;
+    visit(root, &mut |node| {
+        if found.is_some() {
+            return;
+        }
+        let declared = match node.kind() {
+            "function_declaration" => Some(node),
+            "variable_declarator" => node.child_by_field_name("value"),
+            _ => None,
+        };
+        if let Some(declared) = declared
+            && let Some(declared_name) = node.child_by_field_name("name")
+            && &text[declared_name.byte_range()] == name
+        {
+            found = Some(declared);
+        }
+    });
+    found
+}
+
+/// The classes declared in the script `document`, with the range of their
+/// name, in the order they're declared
+pub(crate) fn classes(document: &Document) -> Vec<Symbol> {
+    let Some((_, tree)) = parse_script(document) else {
+        return vec![];
+    };
+    let text = document.text();
+    let mut classes = vec![];
+    visit(tree.root_node(), &mut |node| {
+        if matches!(
+            node.kind(),
+            "class_declaration" | "abstract_class_declaration"
+        ) && let Some(name) = node.child_by_field_name("name")
+        {
+            classes.push(Symbol {
+                name: text[name.byte_range()].to_owned(),
+                range: name.byte_range(),
+            });
+        }
+    });
+    classes
+}
+
+pub(crate) fn visit<'tree>(node: Node<'tree>, f: &mut impl FnMut(Node<'tree>)) {
+    f(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        visit(child, f);
+    }
+}
+
+/// Parses `document` as JavaScript when it's a `.js` file, else as TypeScript
+pub(crate) fn parse_script(document: &Document) -> Option<(Language, tree_sitter::Tree)> {
+    let language: Language = if document.uri.path().ends_with(".js") {
+        tree_sitter_javascript::LANGUAGE.into()
+    } else {
+        tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()
+    };
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(document.text(), None)?;
+    Some((language, tree))
+}
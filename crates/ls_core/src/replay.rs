@@ -0,0 +1,156 @@
+//! Playing recorded sessions back through the server, to turn real editor
+//! sessions into regression tests.
+//!
+//! A session recorded with [`crate::LServer::record_to`] is replayed with
+//! [`check_golden`], which compares what the server sends now against a
+//! golden file of what it sent when the test was written
+
+use std::{
+    env, fs,
+    io::{self, Cursor, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    LServer,
+    record::{Direction, Recording},
+};
+
+/// Notifications that depend on timing or on how the server was started,
+/// left out of what's compared
+const UNSTABLE_METHODS: &[&str] = &["$/logTrace", "window/logMessage"];
+
+/// Golden files are rewritten instead of compared when this is set
+const UPDATE_GOLDEN_VAR: &str = "MYLS_UPDATE_GOLDEN";
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid recording: {0}")]
+    Recording(#[from] serde_json::Error),
+    #[error("Replayed messages differ from '{golden}':\n{diff}")]
+    Mismatch { golden: String, diff: String },
+    #[error("No golden file at '{0}', run with {UPDATE_GOLDEN_VAR}=1 to write it")]
+    MissingGolden(String),
+}
+
+/// Feeds the inbound messages of `recording` to a new server and returns
+/// the messages it sent, without the unstable ones
+pub fn replay(recording: &Recording) -> Vec<Value> {
+    let mut input = vec![];
+    for message in recording.messages_in(Direction::In) {
+        let content = match message {
+            // content that wasn't valid JSON is sent as it was
+            Value::String(raw) => raw.clone(),
+            message => message.to_string(),
+        };
+        input.extend(format!("Content-Length: {}\r\n\r\n{content}", content.len()).into_bytes());
+    }
+    let output = SharedBuffer::default();
+    LServer::with_io(Cursor::new(input), output.clone()).run();
+    let output = output.0.lock().unwrap_or_else(PoisonError::into_inner);
+    read_messages(&output)
+        .into_iter()
+        .filter(|message| {
+            let method = message.get("method").and_then(Value::as_str);
+            !method.is_some_and(|method| UNSTABLE_METHODS.contains(&method))
+        })
+        .collect()
+}
+
+/// Replays the recording at `recording` and compares the messages the server
+/// sends against the golden file at `golden`, one message per line.
+///
+/// The golden file is written instead when `MYLS_UPDATE_GOLDEN` is set. A
+/// missing one is an error otherwise, so that it can't go unchecked
+pub fn check_golden(recording: &Path, golden: &Path) -> Result<(), ReplayError> {
+    let actual = replay(&Recording::read(recording)?);
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        let mut lines = String::new();
+        for message in &actual {
+            lines.push_str(&message.to_string());
+            lines.push('\n');
+        }
+        fs::write(golden, lines)?;
+        return Ok(());
+    }
+    if !golden.exists() {
+        return Err(ReplayError::MissingGolden(golden.display().to_string()));
+    }
+    let expected = fs::read_to_string(golden)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<Value>>>()?;
+    let diff = diff(&expected, &actual);
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(ReplayError::Mismatch {
+            golden: golden.display().to_string(),
+            diff,
+        })
+    }
+}
+
+/// Describes each message that isn't the same in `expected` and `actual`
+fn diff(expected: &[Value], actual: &[Value]) -> String {
+    let mut diff = String::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let (expected, actual) = (expected.get(index), actual.get(index));
+        if expected != actual {
+            let show =
+                |message: Option<&Value>| message.map_or("<none>".to_owned(), Value::to_string);
+            diff.push_str(&format!(
+                "message {index}:\n  expected: {}\n  actual:   {}\n",
+                show(expected),
+                show(actual)
+            ));
+        }
+    }
+    diff
+}
+
+/// Splits what the server wrote into the JSON of each message
+fn read_messages(output: &[u8]) -> Vec<Value> {
+    let output = String::from_utf8_lossy(output);
+    let mut rest = output.as_ref();
+    let mut messages = vec![];
+    while let Some((header, body)) = rest.split_once("\r\n\r\n") {
+        let Some(length) = header
+            .strip_prefix("Content-Length: ")
+            .and_then(|length| length.parse::<usize>().ok())
+            .filter(|length| *length <= body.len())
+        else {
+            break;
+        };
+        if let Ok(message) = serde_json::from_str(&body[..length]) {
+            messages.push(message);
+        }
+        rest = &body[length..];
+    }
+    messages
+}
+
+/// Output the server writes to that can still be read after it's gone
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Templates bound to a controller by the router rather than by their file
+//! name: ui-router's `$stateProvider.state('orders', { ... })` and ngRoute's
+//! `$routeProvider.when('/orders', { ... })`
+
+use tree_sitter::Node;
+
+use crate::{
+    Workspace,
+    controller::Symbol,
+    document::Document,
+    links::refers_to,
+    registry::{function_name, parse_script, property, string_value, visit},
+    uri::DocumentUri,
+};
+
+/// Methods of `$stateProvider` and `$routeProvider` configuring a route
+const ROUTE_METHODS: &[&str] = &["state", "when"];
+
+/// A template shown by a route, or by one of the named `views` of a state
+#[derive(Debug, Clone)]
+pub(crate) struct Route {
+    /// `templateUrl` as written
+    pub(crate) template_url: String,
+    /// Name the controller is registered with, or of its class or function
+    pub(crate) controller: Option<String>,
+    /// What the template calls the controller, from `controllerAs` or from
+    /// `controller: 'OrderController as order'`
+    pub(crate) controller_as: Option<String>,
+    /// Keys of the state's `resolve`, which the controller is given
+    pub(crate) resolve: Vec<Symbol>,
+}
+
+/// The routes configured in the script `document`
+pub(crate) fn routes(document: &Document) -> Vec<Route> {
+    let Some((_, tree)) = parse_script(document) else {
+        return vec![];
+    };
+    let text = document.text();
+    let mut routes = vec![];
+    visit(tree.root_node(), &mut |node| {
+        if node.kind() != "call_expression" {
+            return;
+        }
+        let is_route = node
+            .child_by_field_name("function")
+            .filter(|function| function.kind() == "member_expression")
+            .and_then(|function| function.child_by_field_name("property"))
+            .is_some_and(|method| ROUTE_METHODS.contains(&&text[method.byte_range()]));
+        if !is_route {
+            return;
+        }
+        // `.state('name', config)`, `.state(config)` or `.when('/path', config)`
+        let Some(config) = node.child_by_field_name("arguments").and_then(|arguments| {
+            let mut cursor = arguments.walk();
+            arguments
+                .named_children(&mut cursor)
+                .filter(|argument| argument.kind() == "object")
+                .last()
+        }) else {
+            return;
+        };
+        let resolve = property(config, "resolve", text)
+            .map(|resolve| keys(resolve, text))
+            .unwrap_or_default();
+        match property(config, "views", text) {
+            Some(views) => {
+                let mut cursor = views.walk();
+                for view in views
+                    .named_children(&mut cursor)
+                    .filter(|pair| pair.kind() == "pair")
+                    .filter_map(|pair| pair.child_by_field_name("value"))
+                {
+                    routes.extend(route(view, &resolve, text));
+                }
+            }
+            None => routes.extend(route(config, &resolve, text)),
+        }
+    });
+    routes
+}
+
+/// The routes of the scripts of `workspace` that show the template at
+/// `template`, with the script each is declared in
+pub(crate) fn routes_of(template: &DocumentUri, workspace: &Workspace) -> Vec<(Document, Route)> {
+    workspace
+        .scripts(template)
+        .into_iter()
+        .flat_map(|script| {
+            workspace
+                .index(&script)
+                .routes
+                .iter()
+                .filter(|route| refers_to(&route.template_url, &script.uri, template))
+                .map(|route| (script.clone(), route.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The route of the state or view config `config`, if it has a `templateUrl`
+fn route(config: Node, resolve: &[Symbol], text: &str) -> Option<Route> {
+    let template_url = property(config, "templateUrl", text)
+        .filter(|url| url.kind() == "string")
+        .and_then(|url| url.named_child(0))?;
+    let mut controller = None;
+    let mut controller_as = property(config, "controllerAs", text)
+        .and_then(|alias| string_value(alias, text))
+        .map(str::to_owned);
+    if let Some(value) = property(config, "controller", text) {
+        match string_value(value, text) {
+            Some(name) => {
+                let mut words = name.split_whitespace();
+                controller = words.next().map(str::to_owned);
+                if let (Some("as"), Some(alias)) = (words.next(), words.next()) {
+                    controller_as = Some(alias.to_owned());
+                }
+            }
+            None => controller = function_name(value, text),
+        }
+    }
+    Some(Route {
+        template_url: text[template_url.byte_range()].to_owned(),
+        controller,
+        controller_as,
+        resolve: resolve.to_vec(),
+    })
+}
+
+/// The keys of the object `object`
+pub(crate) fn keys(object: Node, text: &str) -> Vec<Symbol> {
+    let mut cursor = object.walk();
+    object
+        .named_children(&mut cursor)
+        .filter_map(|pair| match pair.kind() {
+            "pair" => {
+                let key = pair.child_by_field_name("key")?;
+                let key = match key.kind() {
+                    "string" => key.named_child(0)?,
+                    _ => key,
+                };
+                Some(key.byte_range())
+            }
+            "method_definition" => Some(pair.child_by_field_name("name")?.byte_range()),
+            "shorthand_property_identifier" => Some(pair.byte_range()),
+            _ => None,
+        })
+        .map(|range| Symbol {
+            name: text[range.clone()].to_owned(),
+            range,
+        })
+        .collect()
+}
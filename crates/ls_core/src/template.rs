@@ -0,0 +1,508 @@
+use std::ops::Range;
+
+use tree_sitter::{Node, QueryCursor, StreamingIterator};
+
+use crate::{
+    Workspace,
+    config::Interpolation,
+    controller::camel_case,
+    directives::{ValueKind, is_html_attribute, micro_syntax_expressions, ng_value_kind},
+    document::Document,
+    filters::filter_names,
+    registry::{RegistrationKind, ScriptQuery},
+};
+
+/// `alias.member` in an expression, the aliases being told apart from other
+/// objects by [`Template::controller_references`]
+const QUERY_CONTROLLER_EXPRESSION: &str = r#"
+(member_expression
+    object: (identifier) @obj
+    property: (property_identifier) @member
+) @expression"#;
+
+static CONTROLLER_EXPRESSIONS: ScriptQuery = ScriptQuery::new(QUERY_CONTROLLER_EXPRESSION);
+
+/// An attribute of an element in a template
+#[derive(Debug, Clone)]
+pub(crate) struct Attribute {
+    /// Name as written, e.g. `data-ng-if`
+    pub(crate) name: String,
+    pub(crate) name_range: Range<usize>,
+    /// Range of the value without the quotes
+    pub(crate) value_range: Option<Range<usize>>,
+    /// Tag name of the element the attribute is on
+    pub(crate) tag_name: String,
+}
+
+impl Attribute {
+    /// The name AngularJS matches directives with, see [`directive_name`]
+    pub(crate) fn directive_name(&self) -> String {
+        directive_name(&self.name)
+    }
+}
+
+/// The name of an element in a start, end or self-closing tag
+#[derive(Debug, Clone)]
+pub(crate) struct Tag {
+    /// Lowercased, as AngularJS matches it against components and directives
+    pub(crate) name: String,
+    pub(crate) range: Range<usize>,
+}
+
+/// An element, as far as its start tag goes
+#[derive(Debug, Clone)]
+pub(crate) struct Element {
+    /// Lowercased tag name
+    pub(crate) name: String,
+    pub(crate) name_range: Range<usize>,
+    /// Range of the whole start tag, or of the self-closing tag
+    pub(crate) start_tag: Range<usize>,
+}
+
+/// A `vm.member` reference to the controller in a template, `vm` being any of
+/// [`Config::controller_aliases`] or the alias of an enclosing `ng-controller`
+#[derive(Debug, Clone)]
+pub(crate) struct ControllerReference {
+    pub(crate) name: String,
+    /// Range of `member`
+    pub(crate) range: Range<usize>,
+    /// Range of the whole `vm.member`
+    pub(crate) expression_range: Range<usize>,
+    /// Name of the controller of the `ng-controller` the alias is from, `None`
+    /// for the controller the template is bound to
+    pub(crate) controller: Option<String>,
+}
+
+/// A controller set on an element with
+/// `ng-controller="OrderController as order"`
+#[derive(Debug, Clone)]
+pub(crate) struct ControllerScope {
+    /// Name the controller is registered with, or of its class
+    pub(crate) name: String,
+    pub(crate) name_range: Range<usize>,
+    /// What the element and its descendants call the controller, `None`
+    /// when it's only reached through `$scope`
+    pub(crate) alias: Option<String>,
+    /// Range of the whole element
+    pub(crate) element: Range<usize>,
+}
+
+/// What's found in an HTML template
+#[derive(Debug, Default)]
+pub(crate) struct Template {
+    pub(crate) elements: Vec<Element>,
+    pub(crate) tags: Vec<Tag>,
+    pub(crate) attributes: Vec<Attribute>,
+    /// Ranges of the AngularJS expressions in the template, either attribute
+    /// values evaluated as expressions or the insides of `{{ }}`
+    pub(crate) expressions: Vec<Range<usize>>,
+    /// The `ng-controller`s of the template, outer ones first
+    pub(crate) scopes: Vec<ControllerScope>,
+}
+
+impl Template {
+    pub(crate) fn parse(document: &Document, workspace: &Workspace) -> Self {
+        let mut parser = tree_sitter::Parser::new();
+        let mut template = Template::default();
+        if parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .is_err()
+        {
+            return template;
+        }
+        let Some(tree) = parser.parse(document.text(), None) else {
+            return template;
+        };
+        let interpolation = &workspace.config.interpolation;
+        template.visit(tree.root_node(), document.text(), interpolation);
+        template.add_bindings(document, workspace);
+        template
+    }
+
+    /// Adds the values of the `<`, `=` and `&` bindings set on the custom
+    /// elements of the template, the only ones of their attributes AngularJS
+    /// evaluates. The attributes of elements no component is found for, other
+    /// than HTML ones, are taken for such bindings. Components are only looked
+    /// up when the template has custom elements
+    fn add_bindings(&mut self, document: &Document, workspace: &Workspace) {
+        let interpolation = &workspace.config.interpolation;
+        let candidates: Vec<_> = self
+            .attributes
+            .iter()
+            .filter(|attribute| {
+                attribute.tag_name.contains('-')
+                    && !attribute.directive_name().starts_with("ng-")
+                    && !is_html_attribute(&attribute.name)
+            })
+            .filter_map(|attribute| Some((attribute, attribute.value_range.clone()?)))
+            .filter(|(_, value)| {
+                interpolation.start.is_empty()
+                    || !document.text()[value.clone()].contains(&interpolation.start)
+            })
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let components: Vec<_> = workspace
+            .scripts(&document.uri)
+            .iter()
+            .flat_map(|script| workspace.index(script).registrations.clone())
+            .filter(|registration| registration.kind == RegistrationKind::Component)
+            .collect();
+        let mut bindings = vec![];
+        for (attribute, value) in candidates {
+            let name = camel_case(&attribute.tag_name);
+            let binding = camel_case(&attribute.directive_name());
+            let is_expression = match components.iter().find(|component| component.name == name) {
+                Some(component) => component.bindings.iter().any(|known| {
+                    known.attribute == binding && known.mode.starts_with(['<', '=', '&'])
+                }),
+                None => true,
+            };
+            if is_expression {
+                bindings.push(value);
+            }
+        }
+        self.expressions.extend(bindings);
+        self.expressions.sort_by_key(|expression| expression.start);
+    }
+
+    fn visit(&mut self, node: Node, text: &str, interpolation: &Interpolation) {
+        match node.kind() {
+            "attribute" => self.visit_attribute(node, text, interpolation),
+            "start_tag" | "self_closing_tag" => {
+                if let Some(name) = node
+                    .child_by_field_name("name")
+                    .or_else(|| node.child(1))
+                    .filter(|name| name.kind() == "tag_name")
+                {
+                    self.elements.push(Element {
+                        name: text[name.byte_range()].to_lowercase(),
+                        name_range: name.byte_range(),
+                        start_tag: node.byte_range(),
+                    });
+                }
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor) {
+                    self.visit(child, text, interpolation);
+                }
+            }
+            "tag_name" => self.tags.push(Tag {
+                name: text[node.byte_range()].to_lowercase(),
+                range: node.byte_range(),
+            }),
+            "text" => self.add_interpolations(node.byte_range(), text, interpolation),
+            _ => {
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor) {
+                    self.visit(child, text, interpolation);
+                }
+            }
+        }
+    }
+
+    fn visit_attribute(&mut self, node: Node, text: &str, interpolation: &Interpolation) {
+        let mut cursor = node.walk();
+        let mut name = None;
+        let mut value_range = None;
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "attribute_name" => name = Some(child),
+                "attribute_value" => value_range = Some(child.byte_range()),
+                "quoted_attribute_value" => {
+                    // an empty value has no `attribute_value` inside the quotes
+                    let start = child.start_byte() + 1;
+                    let end = child.end_byte().saturating_sub(1).max(start);
+                    value_range = Some(start..end);
+                }
+                _ => {}
+            }
+        }
+        let Some(name) = name else {
+            return;
+        };
+        let tag_name = node
+            .parent()
+            .and_then(|tag| tag.child_by_field_name("name").or_else(|| tag.child(1)))
+            .filter(|tag_name| tag_name.kind() == "tag_name")
+            .map(|tag_name| text[tag_name.byte_range()].to_lowercase())
+            .unwrap_or_default();
+        let attribute = Attribute {
+            name: text[name.byte_range()].to_owned(),
+            name_range: name.byte_range(),
+            value_range,
+            tag_name,
+        };
+        if let Some(value_range) = attribute.value_range.clone()
+            && attribute.directive_name() == "ng-controller"
+            && let Some(element) = node.parent().and_then(|tag| tag.parent())
+        {
+            self.scopes.extend(controller_scope(
+                text,
+                value_range.clone(),
+                element.byte_range(),
+            ));
+        }
+        if let Some(value_range) = attribute.value_range.clone() {
+            match value_kind(&attribute) {
+                Some(ValueKind::MicroSyntax) => {
+                    let value = &text[value_range.clone()];
+                    self.expressions.extend(
+                        micro_syntax_expressions(&attribute.directive_name(), value)
+                            .into_iter()
+                            .map(|range| {
+                                value_range.start + range.start..value_range.start + range.end
+                            }),
+                    );
+                }
+                _ if text[value_range.clone()].contains(&interpolation.start) => {
+                    self.add_interpolations(value_range, text, interpolation);
+                }
+                Some(ValueKind::Expression) => self.expressions.push(value_range),
+                _ => {}
+            }
+        }
+        self.attributes.push(attribute);
+    }
+
+    /// Adds the insides of each `{{ }}` in `range`
+    fn add_interpolations(
+        &mut self,
+        range: Range<usize>,
+        text: &str,
+        interpolation: &Interpolation,
+    ) {
+        if interpolation.start.is_empty() || interpolation.end.is_empty() {
+            return;
+        }
+        let mut rest = range.start;
+        while let Some(start) = text[rest..range.end].find(&interpolation.start) {
+            let start = rest + start + interpolation.start.len();
+            let Some(end) = text[start..range.end].find(&interpolation.end) else {
+                break;
+            };
+            let end = start + end;
+            self.expressions.push(start..end);
+            rest = end + interpolation.end.len();
+        }
+    }
+
+    /// The tag whose name is at `offset`
+    pub(crate) fn tag_at(&self, offset: usize) -> Option<&Tag> {
+        self.tags
+            .iter()
+            .find(|tag| tag.range.contains(&offset) || tag.range.end == offset)
+    }
+
+    /// The attributes in the start tag of `element`
+    pub(crate) fn attributes_of<'a>(
+        &'a self,
+        element: &Element,
+    ) -> impl Iterator<Item = &'a Attribute> {
+        let start_tag = element.start_tag.clone();
+        self.attributes
+            .iter()
+            .filter(move |attribute| start_tag.contains(&attribute.name_range.start))
+    }
+
+    /// The element whose start tag `offset` is in, where an attribute name
+    /// could be typed: past the tag name and outside attribute values
+    pub(crate) fn attribute_position(&self, offset: usize) -> Option<&Element> {
+        let element = self
+            .elements
+            .iter()
+            .find(|element| element.name_range.end < offset && offset < element.start_tag.end)?;
+        let in_value = self.attributes_of(element).any(|attribute| {
+            attribute
+                .value_range
+                .as_ref()
+                .is_some_and(|value| value.start <= offset && offset <= value.end)
+        });
+        (!in_value).then_some(element)
+    }
+
+    /// The attribute whose name is at `offset`
+    pub(crate) fn attribute_at(&self, offset: usize) -> Option<&Attribute> {
+        self.attributes.iter().find(|attribute| {
+            attribute.name_range.contains(&offset) || attribute.name_range.end == offset
+        })
+    }
+
+    /// The class name at `offset` in the value of a `class` attribute
+    pub(crate) fn class_at<'a>(&self, text: &'a str, offset: usize) -> Option<&'a str> {
+        let value = self
+            .attributes
+            .iter()
+            .filter(|attribute| attribute.name.eq_ignore_ascii_case("class"))
+            .filter_map(|attribute| attribute.value_range.clone())
+            .find(|value| value.contains(&offset) || value.end == offset)?;
+        let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        let start = text[value.start..offset]
+            .rfind(|c| !is_name(c))
+            .map_or(value.start, |at| value.start + at + 1);
+        let end = text[offset..value.end]
+            .find(|c| !is_name(c))
+            .map_or(value.end, |at| offset + at);
+        Some(&text[start..end]).filter(|class| !class.is_empty())
+    }
+
+    /// Ranges of the names of the filters applied in the expressions of the
+    /// template, see [`filter_names`]
+    pub(crate) fn filters<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.expressions
+            .iter()
+            .flat_map(|expression| filter_names(text, expression.clone()))
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Range of the filter name at `offset`
+    pub(crate) fn filter_at(&self, text: &str, offset: usize) -> Option<Range<usize>> {
+        self.filters(text)
+            .find(|name| name.contains(&offset) || name.end == offset)
+    }
+
+    /// Whether `offset` is where the name of a filter is typed, right after
+    /// a `|` in an expression or in the name that follows it
+    pub(crate) fn is_filter_position(&self, text: &str, offset: usize) -> bool {
+        self.expressions
+            .iter()
+            .filter(|expression| expression.start <= offset && offset <= expression.end)
+            .any(|expression| {
+                let before = text[expression.start..offset]
+                    .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+                    .trim_end();
+                before.ends_with('|') && !before.ends_with("||")
+            })
+    }
+
+    /// The innermost `ng-controller` that calls its controller `alias` and
+    /// whose element `offset` is in
+    fn scope_at(&self, alias: &str, offset: usize) -> Option<&ControllerScope> {
+        self.scopes
+            .iter()
+            .filter(|scope| {
+                scope.alias.as_deref() == Some(alias) && scope.element.contains(&offset)
+            })
+            .min_by_key(|scope| scope.element.len())
+    }
+
+    /// The `ng-controller` whose controller name is at `offset`
+    pub(crate) fn scope_name_at(&self, offset: usize) -> Option<&ControllerScope> {
+        self.scopes
+            .iter()
+            .find(|scope| scope.name_range.contains(&offset) || scope.name_range.end == offset)
+    }
+
+    /// Every `vm.member` in the expressions of the template, `vm` being one of
+    /// `aliases`, the names of the controller the template is bound to, or
+    /// the alias of an enclosing `ng-controller`
+    pub(crate) fn controller_references(
+        &self,
+        document: &Document,
+        aliases: &[String],
+    ) -> Vec<ControllerReference> {
+        let language = tree_sitter_javascript::LANGUAGE.into();
+        let mut parser = tree_sitter::Parser::new();
+        if parser.set_language(&language).is_err() {
+            return vec![];
+        }
+        let Some(query) = CONTROLLER_EXPRESSIONS.get(&language) else {
+            return vec![];
+        };
+        let alias_index = query.capture_index_for_name("obj");
+        let member_index = query.capture_index_for_name("member");
+        let expression_index = query.capture_index_for_name("expression");
+        let mut references = vec![];
+        for expression in &self.expressions {
+            let source = &document.text()[expression.clone()];
+            let Some(tree) = parser.parse(source, None) else {
+                continue;
+            };
+            let mut cursor = QueryCursor::new();
+            let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+            while let Some(m) = matches.next() {
+                let offset_range = |index| {
+                    m.captures
+                        .iter()
+                        .find(|capture| Some(capture.index) == index)
+                        .map(|capture| {
+                            let range = capture.node.byte_range();
+                            expression.start + range.start..expression.start + range.end
+                        })
+                };
+                let (Some(alias), Some(range), Some(expression_range)) = (
+                    offset_range(alias_index),
+                    offset_range(member_index),
+                    offset_range(expression_index),
+                ) else {
+                    continue;
+                };
+                let alias = &document.text()[alias];
+                // an `ng-controller` alias only means something inside its element
+                let controller = match self.scope_at(alias, range.start) {
+                    Some(scope) => Some(scope.name.clone()),
+                    None if aliases.iter().any(|known| known == alias) => None,
+                    None => continue,
+                };
+                references.push(ControllerReference {
+                    name: document.text()[range.clone()].to_owned(),
+                    range,
+                    expression_range,
+                    controller,
+                });
+            }
+        }
+        references
+    }
+}
+
+/// The `ng-controller` whose value is at `value` of `text`, on the element at
+/// `element`: `OrderController as order`, or only `OrderController`
+fn controller_scope(
+    text: &str,
+    value: Range<usize>,
+    element: Range<usize>,
+) -> Option<ControllerScope> {
+    let mut words = text[value.clone()]
+        .split(|c: char| c.is_ascii_whitespace())
+        .scan(value.start, |start, word| {
+            let range = *start..*start + word.len();
+            *start = range.end + 1;
+            Some((word, range))
+        })
+        .filter(|(word, _)| !word.is_empty());
+    let (name, name_range) = words.next()?;
+    let alias = match (words.next(), words.next()) {
+        (Some(("as", _)), Some((alias, _))) => Some(alias.to_owned()),
+        _ => None,
+    };
+    Some(ControllerScope {
+        name: name.to_owned(),
+        name_range,
+        alias,
+        element,
+    })
+}
+
+/// Normalizes an attribute name the way AngularJS does before matching it
+/// against directives: `data-` and `x-` prefixes are dropped and `:` and `_`
+/// are treated as `-`, so `data-ng:model` is `ng-model`
+pub(crate) fn directive_name(attribute_name: &str) -> String {
+    let name = attribute_name.to_lowercase().replace([':', '_'], "-");
+    name.strip_prefix("data-")
+        .or_else(|| name.strip_prefix("x-"))
+        .map(str::to_owned)
+        .unwrap_or(name)
+}
+
+/// What the value of `attribute` holds, as the catalog says for `ng-*`
+/// directives. The bindings of components are only known once they're looked
+/// up, see [`Template::add_bindings`]
+fn value_kind(attribute: &Attribute) -> Option<ValueKind> {
+    let name = attribute.directive_name();
+    if name.starts_with("ng-") {
+        ng_value_kind(&name)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,417 @@
+//! A client for driving a server in the same process, for integration tests.
+//!
+//! The server runs on its own thread and talks to the [`TestClient`] over
+//! in-memory channels, exactly as it would over stdio:
+//!
+//! ```no_run
+//! use ls_core::testing::TestClient;
+//!
+//! let mut client = TestClient::with_fixtures("test");
+//! client.initialize();
+//! client.open_fixture("test.html");
+//! let definition = client.definition("test.html", 4, 17);
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use lsp_types::{
+    ClientCapabilities, CompletionItem, CompletionParams, CompletionResponse, Diagnostic,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams,
+    InitializeParams, InitializeResult, InitializedParams, Position, TextDocumentIdentifier,
+    TextDocumentItem, TextDocumentPositionParams, Uri,
+    notification::{self, Notification},
+    request::{self, Request},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{LServer, uri::DocumentUri};
+
+/// How long to wait for the server before failing the test
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The error of a request the server failed
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseError {
+    pub code: i32,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+/// A client connected to a server running in-process.
+///
+/// Files are named by their path relative to the fixture directory the client
+/// was created with. The helpers panic when the server fails, as is expected
+/// of a test; [`Self::request`] returns the error instead
+pub struct TestClient {
+    input: Option<Sender<Vec<u8>>>,
+    output: Receiver<Value>,
+    server: Option<JoinHandle<()>>,
+    fixtures: PathBuf,
+    next_id: i64,
+    /// Notifications received while waiting for a response
+    notifications: VecDeque<Value>,
+}
+
+impl TestClient {
+    /// A client for a server whose files are under `fixtures`.
+    /// Files not opened with [`Self::open`] are read from there by the server
+    pub fn with_fixtures(fixtures: impl AsRef<Path>) -> Self {
+        let fixtures = std::path::absolute(fixtures.as_ref()).unwrap_or_else(|err| {
+            panic!(
+                "invalid fixture directory '{}': {err}",
+                fixtures.as_ref().display()
+            )
+        });
+        let (input, server_input) = mpsc::channel();
+        let (server_output, output) = mpsc::channel();
+        let server = thread::spawn(move || {
+            LServer::with_io(
+                ChannelReader::new(server_input),
+                ChannelWriter::new(server_output),
+            )
+            .run();
+        });
+        Self {
+            input: Some(input),
+            output,
+            server: Some(server),
+            fixtures,
+            next_id: 0,
+            notifications: VecDeque::new(),
+        }
+    }
+
+    /// The URI of the fixture at `path`
+    pub fn uri(&self, path: &str) -> Uri {
+        let uri = DocumentUri::from_file_path(&self.fixtures.join(path));
+        Uri::try_from(&uri).expect("fixture paths make valid URIs")
+    }
+
+    /// Initializes the server with capabilities that leave everything to their
+    /// defaults, and sends `initialized`
+    pub fn initialize(&mut self) -> InitializeResult {
+        self.initialize_with(InitializeParams {
+            capabilities: ClientCapabilities::default(),
+            ..Default::default()
+        })
+    }
+
+    pub fn initialize_with(&mut self, params: InitializeParams) -> InitializeResult {
+        let result = self.expect::<request::Initialize>(params);
+        self.notify::<notification::Initialized>(InitializedParams {});
+        result
+    }
+
+    /// Opens the fixture at `path` with `text` instead of what's on disk
+    pub fn open(&mut self, path: &str, text: &str) {
+        let language_id = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("ts") => "typescript",
+            Some("js") => "javascript",
+            Some(extension) => extension,
+            None => "plaintext",
+        };
+        self.notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                self.uri(path),
+                language_id.to_owned(),
+                1,
+                text.to_owned(),
+            ),
+        });
+    }
+
+    /// Opens the fixture at `path` as it is on disk
+    pub fn open_fixture(&mut self, path: &str) {
+        let text = fs::read_to_string(self.fixtures.join(path))
+            .unwrap_or_else(|err| panic!("couldn't read fixture '{path}': {err}"));
+        self.open(path, &text);
+    }
+
+    /// `textDocument/definition` at the 0-based `line` and `character` of `path`
+    pub fn definition(
+        &mut self,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Option<GotoDefinitionResponse> {
+        let params = GotoDefinitionParams {
+            text_document_position_params: self.position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.expect::<request::GotoDefinition>(params)
+    }
+
+    /// `textDocument/hover` at the 0-based `line` and `character` of `path`
+    pub fn hover(&mut self, path: &str, line: u32, character: u32) -> Option<Hover> {
+        let params = HoverParams {
+            text_document_position_params: self.position(path, line, character),
+            work_done_progress_params: Default::default(),
+        };
+        self.expect::<request::HoverRequest>(params)
+    }
+
+    /// `textDocument/completion` at the 0-based `line` and `character` of `path`
+    pub fn completion(&mut self, path: &str, line: u32, character: u32) -> Vec<CompletionItem> {
+        let params = CompletionParams {
+            text_document_position: self.position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        };
+        match self.expect::<request::Completion>(params) {
+            Some(CompletionResponse::Array(items)) => items,
+            Some(CompletionResponse::List(list)) => list.items,
+            None => vec![],
+        }
+    }
+
+    /// The diagnostics published next for `path`, skipping those published
+    /// for other documents
+    pub fn diagnostics(&mut self, path: &str) -> Vec<Diagnostic> {
+        let uri = self.uri(path);
+        loop {
+            let params = self.notification::<notification::PublishDiagnostics>();
+            if params.uri == uri {
+                return params.diagnostics;
+            }
+        }
+    }
+
+    fn position(&self, path: &str, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier::new(self.uri(path)),
+            position: Position::new(line, character),
+        }
+    }
+
+    /// Sends the request `R` and waits for its result
+    pub fn request<R: Request>(&mut self, params: R::Params) -> Result<R::Result, ResponseError>
+    where
+        R::Params: Serialize,
+        R::Result: DeserializeOwned,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": R::METHOD,
+            "params": params,
+        }));
+        loop {
+            let message = self.receive();
+            if message.get("id") != Some(&json!(id)) {
+                if message.get("method").is_some() {
+                    self.notifications.push_back(message);
+                }
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                return Err(serde_json::from_value(error.clone())
+                    .unwrap_or_else(|err| panic!("invalid error from the server: {err}")));
+            }
+            let result = message.get("result").cloned().unwrap_or(Value::Null);
+            return Ok(serde_json::from_value(result)
+                .unwrap_or_else(|err| panic!("invalid result for '{}': {err}", R::METHOD)));
+        }
+    }
+
+    /// Like [`Self::request`] but panics if the request failed
+    pub fn expect<R: Request>(&mut self, params: R::Params) -> R::Result
+    where
+        R::Params: Serialize,
+        R::Result: DeserializeOwned,
+    {
+        self.request::<R>(params)
+            .unwrap_or_else(|err| panic!("'{}' failed: {err:?}", R::METHOD))
+    }
+
+    pub fn notify<N: Notification>(&mut self, params: N::Params)
+    where
+        N::Params: Serialize,
+    {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": N::METHOD,
+            "params": params,
+        }));
+    }
+
+    /// The params of the next notification `N` the server sends,
+    /// skipping any other notification
+    pub fn notification<N: Notification>(&mut self) -> N::Params
+    where
+        N::Params: DeserializeOwned,
+    {
+        loop {
+            let message = match self.notifications.pop_front() {
+                Some(message) => message,
+                None => self.receive(),
+            };
+            if message.get("method").and_then(Value::as_str) == Some(N::METHOD) {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                return serde_json::from_value(params)
+                    .unwrap_or_else(|err| panic!("invalid params for '{}': {err}", N::METHOD));
+            }
+        }
+    }
+
+    /// Sends raw `content` as a message, e.g. to check how invalid JSON is handled
+    pub fn send_raw(&mut self, content: &str) {
+        let message = format!("Content-Length: {}\r\n\r\n{content}", content.len());
+        self.input
+            .as_ref()
+            .and_then(|input| input.send(message.into_bytes()).ok())
+            .expect("the server stopped reading");
+    }
+
+    /// Sends `messages` in one go, so that they have all arrived by the time
+    /// the server handles the first, e.g. a request and its cancellation
+    pub fn send_together(&mut self, messages: &[Value]) {
+        let messages = messages
+            .iter()
+            .map(|message| {
+                let message = message.to_string();
+                format!("Content-Length: {}\r\n\r\n{message}", message.len())
+            })
+            .collect::<String>();
+        self.input
+            .as_ref()
+            .and_then(|input| input.send(messages.into_bytes()).ok())
+            .expect("the server stopped reading");
+    }
+
+    fn send(&mut self, message: Value) {
+        self.send_raw(&message.to_string());
+    }
+
+    /// The next message from the server
+    pub fn receive(&mut self) -> Value {
+        match self.output.recv_timeout(TIMEOUT) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => panic!("the server didn't answer in {TIMEOUT:?}"),
+            Err(RecvTimeoutError::Disconnected) => panic!("the server stopped"),
+        }
+    }
+
+    /// Shuts the server down and waits for it to exit
+    pub fn shutdown(mut self) {
+        self.expect::<request::Shutdown>(());
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(input) = self.input.take() {
+            let exit = json!({ "jsonrpc": "2.0", "method": notification::Exit::METHOD });
+            let exit = exit.to_string();
+            let _ =
+                input.send(format!("Content-Length: {}\r\n\r\n{exit}", exit.len()).into_bytes());
+        }
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Server input fed by the client through a channel
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl ChannelReader {
+    fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            buffer: vec![],
+            position: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for ChannelReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position == self.buffer.len() {
+            // a closed channel is the end of the input
+            self.buffer = self.receiver.recv().unwrap_or_default();
+            self.position = 0;
+        }
+        Ok(&self.buffer[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.buffer.len());
+    }
+}
+
+/// Server output split into messages that are sent to the client
+struct ChannelWriter {
+    sender: Sender<Value>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(sender: Sender<Value>) -> Self {
+        Self {
+            sender,
+            buffer: vec![],
+        }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(header_end) = self
+            .buffer
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+        {
+            let header = String::from_utf8_lossy(&self.buffer[..header_end]);
+            let length: usize = header
+                .strip_prefix("Content-Length: ")
+                .and_then(|length| length.parse().ok())
+                .ok_or_else(|| io::Error::other(format!("invalid header: '{header}'")))?;
+            let body_start = header_end + 4;
+            if self.buffer.len() < body_start + length {
+                break;
+            }
+            let message = serde_json::from_slice(&self.buffer[body_start..body_start + length])?;
+            self.buffer.drain(..body_start + length);
+            // the client may be gone already, e.g. when a test failed
+            let _ = self.sender.send(message);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! The `tsconfig.json` at the root of a project, which says what scripts are
+//! part of it: its `files`, `include` and `exclude`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+/// Name of the TypeScript config file looked up at the root of a project
+const TSCONFIG_FILE_NAME: &str = "tsconfig.json";
+
+/// What TypeScript excludes when `exclude` isn't set, besides `outDir`
+const DEFAULT_EXCLUDE: &[&str] = &["node_modules", "bower_components", "jspm_packages"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTsConfig {
+    files: Option<Vec<String>>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    #[serde(default)]
+    compiler_options: CompilerOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptions {
+    out_dir: Option<String>,
+}
+
+/// The scripts a `tsconfig.json` includes
+#[derive(Debug)]
+pub(crate) struct TsConfig {
+    /// Directory of the `tsconfig.json`, which its paths are relative to
+    dir: PathBuf,
+    /// `files`, relative to `dir`
+    files: Vec<PathBuf>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl TsConfig {
+    /// The `tsconfig.json` in `dir`, `None` when there's none or it can't be
+    /// made sense of, in which case every script is part of the project
+    pub(crate) fn find(dir: &Path) -> Option<Self> {
+        let path = dir.join(TSCONFIG_FILE_NAME);
+        let text = fs::read_to_string(&path).ok()?;
+        let config = serde_json::from_str::<RawTsConfig>(&strip_comments(&text))
+            .map_err(|err| err.to_string())
+            .and_then(|raw| Self::new(dir, raw).map_err(|err| err.to_string()));
+        match config {
+            Ok(config) => Some(config),
+            Err(err) => {
+                tracing::debug!("Ignoring '{}': {err}", path.display());
+                None
+            }
+        }
+    }
+
+    fn new(dir: &Path, raw: RawTsConfig) -> Result<Self, globset::Error> {
+        // everything is included unless `files` lists what is
+        let include = match (raw.include, &raw.files) {
+            (Some(include), _) => include,
+            (None, Some(_)) => vec![],
+            (None, None) => vec!["**/*".to_owned()],
+        };
+        let exclude = raw.exclude.unwrap_or_else(|| {
+            DEFAULT_EXCLUDE
+                .iter()
+                .map(|&pattern| pattern.to_owned())
+                .chain(raw.compiler_options.out_dir)
+                .collect()
+        });
+        Ok(Self {
+            dir: dir.to_owned(),
+            files: raw
+                .files
+                .unwrap_or_default()
+                .iter()
+                .map(|file| PathBuf::from(file.trim_start_matches("./")))
+                .collect(),
+            include: globs(&include)?,
+            exclude: globs(&exclude)?,
+        })
+    }
+
+    /// Whether the script at `path` is part of the project. Scripts outside
+    /// the directory of the `tsconfig.json` aren't its to say
+    pub(crate) fn includes(&self, path: &Path) -> bool {
+        let Ok(path) = path.strip_prefix(&self.dir) else {
+            return true;
+        };
+        self.files.iter().any(|file| file == path)
+            || (self.include.is_match(path) && !self.exclude.is_match(path))
+    }
+}
+
+/// `patterns` as TypeScript reads them: `*` doesn't cross directories, and a
+/// pattern naming a directory, without wildcards or an extension, is
+/// everything under it
+fn globs(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let last = pattern.rsplit('/').next().unwrap_or(pattern);
+        let mut add = |pattern: &str| -> Result<(), globset::Error> {
+            builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+            Ok(())
+        };
+        add(pattern)?;
+        if !last.contains(['*', '?', '.']) {
+            add(&format!("{pattern}/**"))?;
+        }
+    }
+    builder.build()
+}
+
+/// `text`, JSON with comments and trailing commas as `tsconfig.json` allows,
+/// as plain JSON
+fn strip_comments(text: &str) -> String {
+    let mut json = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                json.push(c);
+                while let Some(c) = chars.next() {
+                    json.push(c);
+                    match c {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '}' | ']' => {
+                // a trailing comma, with only whitespace after it
+                let end = json.trim_end().len();
+                if json[..end].ends_with(',') {
+                    json.truncate(end - 1);
+                }
+                json.push(c);
+            }
+            _ => json.push(c),
+        }
+    }
+    json
+}
@@ -0,0 +1,335 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+
+use crate::error::{LSError, LSResult};
+
+/// Characters that are percent-encoded in the path of a URI we write.
+/// Non-ASCII characters are always encoded
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Drives whose drive letter URIs were mapped to their WSL mount, one bit
+/// per letter, so that paths under the mount are given back as the same URIs
+static MOUNTED_DRIVES: AtomicU32 = AtomicU32::new(0);
+
+/// The URI of a document, split into its parts with the path percent-decoded.
+///
+/// Two URIs that point to the same document compare equal once parsed,
+/// e.g. `file:///C%3A/app/my%20page.html` and `file:///c:/app/my page.html`,
+/// and [`fmt::Display`] writes the one canonical form of both.
+/// Documents with schemes other than `file` (e.g. `untitled:`) only
+/// exist in memory and have no path on disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct DocumentUri {
+    scheme: String,
+    /// `None` when the URI has no `//` part, as in `untitled:Untitled-1`
+    authority: Option<String>,
+    /// Percent-decoded path
+    path: String,
+    /// Query and fragment, kept as they were sent
+    suffix: String,
+}
+
+impl DocumentUri {
+    pub(crate) fn parse(uri: &str) -> LSResult<Self> {
+        let invalid = || LSError::InvalidParams {
+            message: format!("Invalid URI: '{uri}'"),
+        };
+        let (scheme, rest) = uri.split_once(':').ok_or_else(invalid)?;
+        let is_valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !is_valid_scheme {
+            return Err(invalid());
+        }
+        let (rest, suffix) = match rest.find(['?', '#']) {
+            Some(at) => rest.split_at(at),
+            None => (rest, ""),
+        };
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let (authority, path) = rest.find('/').map_or((rest, ""), |at| rest.split_at(at));
+                (Some(authority.to_lowercase()), path)
+            }
+            None => (None, rest),
+        };
+        let path = percent_decode_str(path)
+            .decode_utf8()
+            .map_err(|_e| invalid())?;
+        Ok(Self {
+            scheme: scheme.to_lowercase(),
+            authority,
+            path: normalize_drive_letter(&path),
+            suffix: suffix.to_owned(),
+        })
+    }
+
+    /// The `file:` URI of an absolute path on disk
+    pub(crate) fn from_file_path(path: &Path) -> Self {
+        let path = path.to_string_lossy();
+        let path = if cfg!(windows) {
+            let path = path.replace('\\', "/");
+            match path.strip_prefix("//") {
+                // UNC path, `\\server\share\file`
+                Some(unc) => {
+                    let (authority, path) = unc.find('/').map_or((unc, ""), |at| unc.split_at(at));
+                    return Self {
+                        scheme: "file".to_owned(),
+                        authority: Some(authority.to_lowercase()),
+                        path: path.to_owned(),
+                        suffix: String::new(),
+                    };
+                }
+                None => format!("/{path}"),
+            }
+        } else {
+            unmount_drive(&path).unwrap_or_else(|| path.into_owned())
+        };
+        Self {
+            scheme: "file".to_owned(),
+            authority: Some(String::new()),
+            path: normalize_drive_letter(&path),
+            suffix: String::new(),
+        }
+    }
+
+    pub(crate) fn is_file(&self) -> bool {
+        self.scheme == "file"
+    }
+
+    /// Percent-decoded path of the URI
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The same URI with its path replaced
+    pub(crate) fn with_path(&self, path: String) -> Self {
+        Self {
+            path,
+            suffix: String::new(),
+            ..self.clone()
+        }
+    }
+
+    /// The file on disk this URI points to, `None` for non-file schemes.
+    ///
+    /// Drive letter paths like `/c:/app` are what Windows clients send, and are
+    /// mapped to where WSL mounts the drive (`/mnt/c/app`) on other platforms.
+    /// [`Self::from_file_path`] maps paths under that mount back afterwards
+    pub(crate) fn to_file_path(&self) -> Option<PathBuf> {
+        if !self.is_file() {
+            return None;
+        }
+        let authority = self
+            .authority
+            .as_deref()
+            .filter(|authority| !authority.is_empty() && *authority != "localhost");
+        let drive = drive_letter(&self.path);
+        if cfg!(windows) {
+            let path = match (authority, drive) {
+                (Some(host), _) => format!("//{host}{}", self.path),
+                (None, Some(_)) => self.path[1..].to_owned(),
+                (None, None) => self.path.clone(),
+            };
+            Some(PathBuf::from(path.replace('/', "\\")))
+        } else {
+            let path = match (authority, drive) {
+                (Some(host), _) => format!("//{host}{}", self.path),
+                (None, Some(drive)) => {
+                    MOUNTED_DRIVES.fetch_or(drive_bit(drive), Ordering::Relaxed);
+                    format!("/mnt/{drive}{}", &self.path[3..])
+                }
+                (None, None) => self.path.clone(),
+            };
+            Some(PathBuf::from(path))
+        }
+    }
+}
+
+impl fmt::Display for DocumentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.scheme)?;
+        if let Some(authority) = &self.authority {
+            write!(f, "//{authority}")?;
+        }
+        write!(
+            f,
+            "{}{}",
+            utf8_percent_encode(&self.path, PATH),
+            self.suffix
+        )
+    }
+}
+
+impl FromStr for DocumentUri {
+    type Err = LSError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&lsp_types::Uri> for DocumentUri {
+    type Error = LSError;
+
+    fn try_from(uri: &lsp_types::Uri) -> Result<Self, Self::Error> {
+        Self::parse(uri.as_str())
+    }
+}
+
+impl TryFrom<&DocumentUri> for lsp_types::Uri {
+    type Error = LSError;
+
+    fn try_from(uri: &DocumentUri) -> Result<Self, Self::Error> {
+        uri.to_string().parse().map_err(LSError::internal)
+    }
+}
+
+/// The lowercase drive letter of a path like `/C:/app`
+fn drive_letter(path: &str) -> Option<char> {
+    let bytes = path.as_bytes();
+    let is_drive = bytes.len() >= 3
+        && bytes[0] == b'/'
+        && bytes[1].is_ascii_alphabetic()
+        && bytes[2] == b':'
+        && bytes.get(3).is_none_or(|&b| b == b'/');
+    is_drive.then(|| (bytes[1] as char).to_ascii_lowercase())
+}
+
+fn drive_bit(drive: char) -> u32 {
+    1 << (drive as u8 - b'a')
+}
+
+/// The drive letter path of `path` if it's under the WSL mount of a drive
+/// clients sent drive letter URIs for, `/mnt/c/app` giving `/c:/app`
+fn unmount_drive(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/mnt/")?;
+    let mut chars = rest.chars();
+    let drive = chars.next().filter(char::is_ascii_lowercase)?;
+    let rest = chars.as_str();
+    let mounted = MOUNTED_DRIVES.load(Ordering::Relaxed) & drive_bit(drive) != 0;
+    (mounted && (rest.is_empty() || rest.starts_with('/'))).then(|| format!("/{drive}:{rest}"))
+}
+
+/// Lowercases the drive letter, so that `/C:/app` and `/c:/app` compare equal
+fn normalize_drive_letter(path: &str) -> String {
+    match drive_letter(path) {
+        Some(drive) => format!("/{drive}{}", &path[2..]),
+        None => path.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(uri: &str) -> DocumentUri {
+        DocumentUri::parse(uri).unwrap()
+    }
+
+    #[test]
+    fn paths_are_percent_decoded() {
+        let uri = parse("file:///app/my%20page%C3%A9.html?x=%20#top");
+        assert_eq!(uri.path(), "/app/my pageé.html");
+        // the query and fragment are left as they are
+        assert_eq!(
+            uri.to_string(),
+            "file:///app/my%20page%C3%A9.html?x=%20#top"
+        );
+    }
+
+    #[test]
+    fn drive_letters_compare_equal_however_written() {
+        let uri = parse("file:///C%3A/app/my%20page.html");
+        assert_eq!(uri, parse("file:///c:/app/my page.html"));
+        assert_eq!(uri.path(), "/c:/app/my page.html");
+        assert_eq!(uri.to_string(), "file:///c:/app/my%20page.html");
+        // only a single letter followed by a colon is a drive
+        assert_eq!(parse("file:///Cd:/app").path(), "/Cd:/app");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn drive_letters_map_to_wsl_mounts() {
+        assert_eq!(
+            parse("file:///C:/app/page.html").to_file_path(),
+            Some(PathBuf::from("/mnt/c/app/page.html"))
+        );
+        assert_eq!(
+            parse("file:///c:").to_file_path(),
+            Some(PathBuf::from("/mnt/c"))
+        );
+        assert_eq!(
+            parse("file://server/share/page.html").to_file_path(),
+            Some(PathBuf::from("//server/share/page.html"))
+        );
+        assert_eq!(
+            parse("file://localhost/app/page.html").to_file_path(),
+            Some(PathBuf::from("/app/page.html"))
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn file_paths_round_trip() {
+        let path = Path::new("/app/my page#1.html");
+        let uri = DocumentUri::from_file_path(path);
+        assert_eq!(uri.to_string(), "file:///app/my%20page%231.html");
+        assert_eq!(parse(&uri.to_string()), uri);
+        assert_eq!(uri.to_file_path().as_deref(), Some(path));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn drive_letter_uris_round_trip() {
+        for uri in ["file:///q:/app/my%20page.html", "file:///q:"] {
+            let uri = parse(uri);
+            let path = uri.to_file_path().unwrap();
+            assert_eq!(DocumentUri::from_file_path(&path), uri);
+        }
+        // only the mounts of drives clients sent URIs for
+        assert_eq!(
+            DocumentUri::from_file_path(Path::new("/mnt/z/app")).to_string(),
+            "file:///mnt/z/app"
+        );
+        assert_eq!(
+            DocumentUri::from_file_path(Path::new("/mnt/qa/app")).to_string(),
+            "file:///mnt/qa/app"
+        );
+    }
+
+    #[test]
+    fn documents_without_a_path_on_disk() {
+        let uri = parse("untitled:Untitled-1");
+        assert_eq!(uri.to_string(), "untitled:Untitled-1");
+        assert_eq!(uri.to_file_path(), None);
+    }
+
+    #[test]
+    fn invalid_uris() {
+        for uri in ["app/page.html", "1file:///app", ":///app", "file:///%FF"] {
+            assert!(DocumentUri::parse(uri).is_err(), "{uri}");
+        }
+    }
+}
@@ -0,0 +1,459 @@
+//! The analysis engine on its own, for tools that want to reuse what the
+//! server knows about templates and controllers without speaking JSON-RPC.
+//!
+//! ```no_run
+//! use ls_core::{Workspace, protocol::Position};
+//!
+//! let workspace = Workspace::with_root("app");
+//! for template in workspace.diagnostics() {
+//!     for diagnostic in &template.diagnostics {
+//!         println!("{}: {}", template.uri.as_str(), diagnostic.message);
+//!     }
+//! }
+//! ```
+
+use std::{
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use lsp_types::{
+    CompletionItem, Diagnostic, DocumentLink, Location, Position, PositionEncodingKind, Uri,
+};
+use thiserror::Error;
+
+use crate::{
+    completion::completions,
+    config::{Config, ConfigError, TemplateGlobs},
+    controller::controller_members,
+    diagnostics::template_diagnostics,
+    document::{Document, DocumentStore},
+    index::{ScriptCache, ScriptIndex},
+    line_index::PositionEncoding,
+    links::{find_template, find_template_controller, resolve_template_url, template_urls},
+    navigation::{find_definition, find_template_references},
+    tsconfig::TsConfig,
+    uri::DocumentUri,
+};
+
+/// Directories that are never looked into for templates and scripts
+const IGNORED_DIRECTORIES: &[&str] = &["node_modules"];
+
+const TEMPLATE_EXTENSIONS: &[&str] = &["html"];
+
+/// Files components and directives are registered in
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "js"];
+
+#[derive(Error, Debug)]
+#[error("Invalid URI: '{0}'")]
+pub struct InvalidUri(pub String);
+
+/// A member of a controller that templates can reach through `vm`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    /// Where the member is declared in its controller
+    pub location: Location,
+}
+
+/// The diagnostics of one template
+#[derive(Debug, Clone)]
+pub struct TemplateDiagnostics {
+    pub uri: Uri,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Documents that are analysed together.
+///
+/// Documents are the ones opened with [`Self::open`], and files on disk for the
+/// rest. Positions are counted in UTF-16 code units unless
+/// [`Self::with_position_encoding`] says otherwise, and the default [`Config`]
+/// is used until [`Self::set_config`] replaces it
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub(crate) documents: DocumentStore,
+    pub(crate) encoding: PositionEncoding,
+    pub(crate) config: Config,
+    templates: TemplateGlobs,
+    /// Directory templates are looked for in by [`Self::diagnostics`], and
+    /// that the `include` and `exclude` globs are relative to
+    root: Option<PathBuf>,
+    /// What's known of the scripts, until they change
+    scripts: ScriptCache,
+    /// The `tsconfig.json` of the root, which says what scripts are part of
+    /// the project
+    tsconfig: Option<TsConfig>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A workspace for the project in the directory `root`. When the root
+    /// has a `tsconfig.json`, only the scripts it includes are looked into
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            tsconfig: TsConfig::find(&root),
+            root: Some(root),
+            ..Self::default()
+        }
+    }
+
+    /// Counts positions in `encoding`. Encodings other than UTF-8, UTF-16 and
+    /// UTF-32 fall back to UTF-16
+    pub fn with_position_encoding(mut self, encoding: PositionEncodingKind) -> Self {
+        self.encoding = PositionEncoding::negotiate(Some(&[encoding]));
+        self
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Analyses documents with `config` from now on
+    pub fn set_config(&mut self, config: Config) -> Result<(), ConfigError> {
+        self.templates = TemplateGlobs::new(&config)?;
+        self.config = config;
+        self.scripts.clear();
+        Ok(())
+    }
+
+    /// Adds a document, or replaces it if it's already open, so that `text`
+    /// is used instead of what's on disk
+    pub fn open(&mut self, uri: &Uri, text: impl Into<String>) -> Result<(), InvalidUri> {
+        let uri = document_uri(uri)?;
+        let version = self
+            .documents
+            .get(&uri)
+            .and_then(|document| document.version)
+            .map_or(0, |version| version + 1);
+        self.forget(&uri);
+        self.documents.open(uri, text.into(), version);
+        Ok(())
+    }
+
+    /// Removes a document added with [`Self::open`]
+    pub fn close(&mut self, uri: &Uri) {
+        if let Ok(uri) = document_uri(uri) {
+            self.forget(&uri);
+            self.documents.close(&uri);
+        }
+    }
+
+    /// The open document at `uri`, or else the file it points to
+    pub fn document(&self, uri: &Uri) -> Option<Document> {
+        self.documents.get_or_read(&document_uri(uri).ok()?)
+    }
+
+    /// Where the controller member used at `position` of the template at `uri`
+    /// is declared. Scripts have templates embedded in them too
+    pub fn resolve_definition(&self, uri: &Uri, position: Position) -> Option<Location> {
+        let document = self.document(uri)?;
+        let offset = document.offset(position, self.encoding);
+        let (controller, range) = find_definition(&document, offset, self)?;
+        self.location(&controller, range)
+    }
+
+    /// What can be typed at `position` of the template at `uri`, or of the
+    /// template embedded in the script at `uri`
+    pub fn completions(&self, uri: &Uri, position: Position) -> Vec<CompletionItem> {
+        let Some(document) = self.document(uri) else {
+            return vec![];
+        };
+        let offset = document.offset(position, self.encoding);
+        completions(&document, offset, self)
+    }
+
+    /// The `templateUrl`s of the script at `uri`, linked to the templates
+    /// they name
+    pub fn document_links(&self, uri: &Uri) -> Vec<DocumentLink> {
+        let Some(script) = self
+            .document(uri)
+            .filter(|script| self.is_script(&script.uri))
+        else {
+            return vec![];
+        };
+        template_urls(&script)
+            .into_iter()
+            .filter_map(|url| {
+                let template = resolve_template_url(&url.url, &script.uri, self)?;
+                Some(DocumentLink {
+                    range: script.range(url.range.start, url.range.end, self.encoding),
+                    target: Uri::try_from(&template).ok(),
+                    tooltip: None,
+                    data: None,
+                })
+            })
+            .collect()
+    }
+
+    /// The template of the controller or component at `uri`, from its
+    /// `templateUrl` or else the templates bound to it
+    pub fn template_of(&self, uri: &Uri) -> Option<Location> {
+        let template = find_template(&document_uri(uri).ok()?, self)?;
+        self.location(&template, 0..0)
+    }
+
+    /// The controller of the template at `uri`, the way back from
+    /// [`Self::template_of`]
+    pub fn controller_of(&self, uri: &Uri) -> Option<Location> {
+        let controller = find_template_controller(&document_uri(uri).ok()?, self)?;
+        self.location(&controller, 0..0)
+    }
+
+    /// Members declared in the controller at `uri`
+    pub fn members(&self, uri: &Uri) -> Vec<Member> {
+        let Some(controller) = self.document(uri) else {
+            return vec![];
+        };
+        controller_members(&controller)
+            .into_iter()
+            .filter_map(|member| {
+                Some(Member {
+                    location: self.location(&controller, member.range)?,
+                    name: member.name,
+                })
+            })
+            .collect()
+    }
+
+    /// Where templates use `member` through `vm`
+    pub fn template_references(&self, member: &Member) -> Vec<Location> {
+        let Ok(controller) = document_uri(&member.location.uri) else {
+            return vec![];
+        };
+        find_template_references(&controller, &member.name, self)
+            .into_iter()
+            .filter_map(|(template, range)| self.location(&template, range))
+            .collect()
+    }
+
+    /// Problems in the template at `uri`, or in the templates embedded in the
+    /// script at `uri`
+    pub fn template_diagnostics(&self, uri: &Uri) -> Vec<Diagnostic> {
+        self.document(uri)
+            .map(|document| template_diagnostics(&document, self))
+            .unwrap_or_default()
+    }
+
+    /// Problems in each template of the workspace that has any: those open
+    /// and those under the root, then the scripts with templates embedded in
+    /// them. They're looked for as the iterator advances
+    pub fn diagnostics(&self) -> impl Iterator<Item = TemplateDiagnostics> + '_ {
+        let templates = self
+            .templates()
+            .into_iter()
+            .filter_map(|uri| self.documents.get_or_read(&uri));
+        let scripts = self.scripts_in(self.root.clone());
+        templates.chain(scripts).filter_map(|document| {
+            let diagnostics = template_diagnostics(&document, self);
+            if diagnostics.is_empty() {
+                return None;
+            }
+            Some(TemplateDiagnostics {
+                uri: document.uri().ok()?,
+                diagnostics,
+            })
+        })
+    }
+
+    /// The templates of the workspace: those open, then those under the root
+    pub(crate) fn templates(&self) -> Vec<DocumentUri> {
+        let mut templates: Vec<DocumentUri> = self
+            .documents
+            .iter()
+            .map(|document| document.uri.clone())
+            .filter(|uri| self.is_template(uri))
+            .collect();
+        let mut paths = vec![];
+        if let Some(root) = &self.root
+            && let Err(err) = find_templates(root, &mut paths)
+        {
+            tracing::debug!("Couldn't list the templates of '{}': {err}", root.display());
+        }
+        paths.sort();
+        templates.extend(
+            paths
+                .iter()
+                .filter_map(|path| {
+                    Some(DocumentUri::from_file_path(
+                        &std::path::absolute(path).ok()?,
+                    ))
+                })
+                .filter(|uri| self.is_template(uri) && self.documents.get(uri).is_none()),
+        );
+        templates
+    }
+
+    /// The TypeScript and JavaScript files of the workspace: those open and
+    /// those under the root, or in the directory of `near` without a root
+    pub(crate) fn scripts(&self, near: &DocumentUri) -> Vec<Document> {
+        let dir = self.root.clone().or_else(|| {
+            near.to_file_path()
+                .and_then(|path| path.parent().map(Path::to_owned))
+        });
+        self.scripts_in(dir)
+    }
+
+    /// The scripts that are open, then those under `dir`, leaving out those
+    /// that aren't part of the project, see [`Self::is_project_script`]
+    fn scripts_in(&self, dir: Option<PathBuf>) -> Vec<Document> {
+        let mut uris: Vec<DocumentUri> = self
+            .documents
+            .iter()
+            .map(|document| document.uri.clone())
+            .filter(|uri| self.is_script(uri))
+            .collect();
+        if let Some(dir) = dir {
+            let listing = self.scripts.listing(&dir, || list_scripts(&dir));
+            uris.extend(
+                listing
+                    .iter()
+                    .filter(|uri| self.documents.get(uri).is_none())
+                    .cloned(),
+            );
+        }
+        uris.retain(|uri| self.is_project_script(uri));
+        uris.sort_by(|a, b| a.path().cmp(b.path()));
+        uris.iter()
+            .filter_map(|uri| match self.documents.get(uri) {
+                Some(document) => Some(document.clone()),
+                None => self.scripts.file(uri, || self.documents.get_or_read(uri)),
+            })
+            .collect()
+    }
+
+    /// The registrations, routes, `templateUrl`s and classes of `script`, one of
+    /// [`Self::scripts`]
+    pub(crate) fn index(&self, script: &Document) -> Arc<ScriptIndex> {
+        self.scripts.index(script)
+    }
+
+    /// Drops what's known of the script at `uri`, which was opened, changed,
+    /// closed, or changed on disk
+    pub(crate) fn forget(&self, uri: &DocumentUri) {
+        self.scripts.forget(uri);
+    }
+
+    /// Lists the scripts on disk again next time they're needed, as some
+    /// were created or deleted
+    pub(crate) fn forget_listings(&self) {
+        self.scripts.forget_listings();
+    }
+
+    /// Whether the document at `uri` is a TypeScript or JavaScript file
+    pub(crate) fn is_script(&self, uri: &DocumentUri) -> bool {
+        has_extension(uri.path(), SCRIPT_EXTENSIONS)
+    }
+
+    /// Whether the file at `uri` gets diagnostics: a template, or a script of
+    /// the project, for the templates embedded in it
+    pub(crate) fn has_diagnostics(&self, uri: &DocumentUri) -> bool {
+        self.is_template(uri) || (self.is_script(uri) && self.is_project_script(uri))
+    }
+
+    /// Whether the script at `uri` is part of the project: the `exclude`
+    /// globs of the config don't match it, and the `tsconfig.json` of the
+    /// root includes it if there's one
+    pub(crate) fn is_project_script(&self, uri: &DocumentUri) -> bool {
+        let Some(path) = uri.to_file_path() else {
+            return true;
+        };
+        let relative = match &self.root {
+            Some(root) => path.strip_prefix(root).unwrap_or(&path),
+            None => &path,
+        };
+        !self.templates.is_excluded(relative)
+            && self
+                .tsconfig
+                .as_ref()
+                .is_none_or(|tsconfig| tsconfig.includes(&path))
+    }
+
+    /// Whether the document at `uri` is a template according to the
+    /// `include` and `exclude` globs of the config
+    pub(crate) fn is_template(&self, uri: &DocumentUri) -> bool {
+        let Some(path) = uri.to_file_path() else {
+            return false;
+        };
+        let path = match &self.root {
+            Some(root) => path.strip_prefix(root).unwrap_or(&path),
+            None => &path,
+        };
+        self.templates.is_template(path)
+    }
+
+    /// Where `range` of `document` is, `None` when its URI can't be sent back
+    fn location(&self, document: &Document, range: Range<usize>) -> Option<Location> {
+        Some(Location::new(
+            document.uri().ok()?,
+            document.range(range.start, range.end, self.encoding),
+        ))
+    }
+}
+
+fn document_uri(uri: &Uri) -> Result<DocumentUri, InvalidUri> {
+    DocumentUri::try_from(uri).map_err(|_err| InvalidUri(uri.as_str().to_owned()))
+}
+
+/// The scripts under `dir`
+fn list_scripts(dir: &Path) -> Vec<DocumentUri> {
+    let mut paths = vec![];
+    if let Err(err) = find_scripts(dir, &mut paths) {
+        tracing::debug!("Couldn't list the scripts of '{}': {err}", dir.display());
+    }
+    paths
+        .iter()
+        .filter_map(|path| {
+            Some(DocumentUri::from_file_path(
+                &std::path::absolute(path).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Adds the `.html` files under `dir` to `templates`, skipping hidden
+/// directories and `node_modules`
+pub(crate) fn find_templates(dir: &Path, templates: &mut Vec<PathBuf>) -> io::Result<()> {
+    find_files(dir, TEMPLATE_EXTENSIONS, templates)
+}
+
+pub(crate) fn find_scripts(dir: &Path, scripts: &mut Vec<PathBuf>) -> io::Result<()> {
+    find_files(dir, SCRIPT_EXTENSIONS, scripts)
+}
+
+/// Adds the files under `dir` with any of `extensions` to `files`, skipping
+/// hidden directories and `node_modules`
+pub(crate) fn find_files(
+    dir: &Path,
+    extensions: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && !IGNORED_DIRECTORIES.contains(&name.as_ref()) {
+                find_files(&path, extensions, files)?;
+            }
+        } else if has_extension(&name, extensions) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, extension)| extensions.contains(&extension))
+}
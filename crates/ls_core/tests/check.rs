@@ -0,0 +1,58 @@
+use std::{fs, path::Path};
+
+use ls_core::{
+    Config,
+    check::check_project,
+    protocol::{NumberOrString, Position},
+};
+use serde_json::json;
+
+mod common;
+
+use common::FIXTURES;
+
+#[test]
+fn inline_fixture_is_clean() {
+    let files = check_project(&Path::new(FIXTURES).join("inline"), Config::default()).unwrap();
+    assert!(files.is_empty(), "{files:?}");
+}
+
+#[test]
+fn checks_templates_embedded_in_scripts() {
+    let root = std::env::temp_dir().join(format!("myls-check-inline-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("badge.component.ts"),
+        "angular.module('app').component('badge', {\n  bindings: { count: '<' },\n  template: '<b>{{$ctrl.counts}}</b>',\n});\n",
+    )
+    .unwrap();
+    let files = check_project(&root, Config::default());
+    fs::remove_dir_all(&root).unwrap();
+    let files = files.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, root.join("badge.component.ts"));
+    let diagnostic = &files[0].diagnostics[0];
+    assert_eq!(
+        diagnostic.code,
+        Some(NumberOrString::String("unknown-member".to_owned()))
+    );
+    assert_eq!(diagnostic.range.start, Position::new(2, 24));
+}
+
+#[test]
+fn only_included_templates_are_checked() {
+    let root = std::env::temp_dir().join(format!("myls-check-include-{}", std::process::id()));
+    for dir in ["app", "other"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(
+            root.join(dir).join("page.html"),
+            "<p>{{ 'Ada' | nope }}</p>\n",
+        )
+        .unwrap();
+    }
+    let config = Config::from_layers([&json!({ "include": ["app/**/*.html"] })]).unwrap();
+    let files = check_project(&root, config);
+    fs::remove_dir_all(&root).unwrap();
+    let paths: Vec<_> = files.unwrap().into_iter().map(|file| file.path).collect();
+    assert_eq!(paths, [root.join("app/page.html")]);
+}
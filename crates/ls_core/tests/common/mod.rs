@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests, for the fixtures in `/test`
+
+// each test crate uses some of them
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use ls_core::{
+    protocol::{GotoDefinitionResponse, Position, Uri},
+    testing::TestClient,
+};
+
+pub const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../test");
+
+/// A client for a server whose files are the fixtures, not initialized yet
+pub fn fixtures() -> TestClient {
+    TestClient::with_fixtures(FIXTURES)
+}
+
+/// A client for a server whose files are the fixtures, initialized without
+/// workspace folders
+pub fn client() -> TestClient {
+    let mut client = fixtures();
+    client.initialize();
+    client
+}
+
+/// The URI and start of the definition at `line`, `character` of `path`.
+/// Panics if there's more than one location
+pub fn definition(
+    client: &mut TestClient,
+    path: &str,
+    line: u32,
+    character: u32,
+) -> Option<(Uri, Position)> {
+    match client.definition(path, line, character)? {
+        GotoDefinitionResponse::Scalar(location) => Some((location.uri, location.range.start)),
+        _ => panic!("expected a single location"),
+    }
+}
+
+/// The URI of the fixture at `path`, with symbolic links resolved like a
+/// [`ls_core::Workspace`] rooted at the canonical fixtures sees it
+pub fn uri(path: &str) -> Uri {
+    let path = Path::new(FIXTURES).join(path).canonicalize().unwrap();
+    format!("file://{}", path.display()).parse().unwrap()
+}
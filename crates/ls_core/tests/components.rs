@@ -0,0 +1,319 @@
+use std::fs;
+
+use ls_core::{
+    protocol::{
+        CompletionItemKind, Diagnostic, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+        Documentation, FileChangeType, FileEvent, GotoDefinitionResponse, Location, NumberOrString,
+        Position, Range, TextDocumentContentChangeEvent, VersionedTextDocumentIdentifier,
+        notification,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::client;
+
+fn labels(client: &mut TestClient, path: &str, line: u32, character: u32) -> Vec<String> {
+    labels_of(client, path, line, character, None)
+}
+
+/// Labels of the completions of `kind`, or of every completion
+fn labels_of(
+    client: &mut TestClient,
+    path: &str,
+    line: u32,
+    character: u32,
+    kind: Option<CompletionItemKind>,
+) -> Vec<String> {
+    let mut labels: Vec<_> = client
+        .completion(path, line, character)
+        .into_iter()
+        .filter(|item| kind.is_none() || item.kind == kind)
+        .map(|item| item.label)
+        .collect();
+    labels.sort();
+    labels
+}
+
+#[test]
+fn definition_of_binding() {
+    let mut client = client();
+    client.open_fixture("components/user-list.html");
+    let expected = GotoDefinitionResponse::Scalar(Location::new(
+        client.uri("components/components.ts"),
+        Range::new(Position::new(4, 4), Position::new(4, 8)),
+    ));
+    assert_eq!(
+        client.definition("components/user-list.html", 1, 17),
+        Some(expected)
+    );
+}
+
+#[test]
+fn definition_of_decorated_binding() {
+    let mut client = client();
+    client.open("components/page.html", "<search-box on-search=\"go()\">\n");
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.definition("components/page.html", 0, 14)
+    else {
+        panic!("expected the `@Output`");
+    };
+    assert_eq!(location.uri, client.uri("components/SearchBox.ts"));
+    assert_eq!(
+        location.range,
+        Range::new(Position::new(3, 11), Position::new(3, 19))
+    );
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.definition("components/page.html", 0, 3)
+    else {
+        panic!("expected the selector");
+    };
+    assert_eq!(location.range.start, Position::new(0, 24));
+}
+
+#[test]
+fn completion_of_bindings() {
+    let mut client = client();
+    client.open(
+        "components/page.html",
+        "<user-card user=\"1\" ></user-card>\n",
+    );
+    let bindings = Some(CompletionItemKind::PROPERTY);
+    assert_eq!(
+        labels_of(&mut client, "components/page.html", 0, 20, bindings),
+        ["on-save", "title"]
+    );
+    // not in values, nor on elements that aren't components
+    assert!(labels(&mut client, "components/page.html", 0, 17).is_empty());
+    client.open(
+        "components/page.html",
+        "<div ></div><search-box ></search-box>\n",
+    );
+    assert!(labels_of(&mut client, "components/page.html", 0, 5, bindings).is_empty());
+    assert_eq!(
+        labels_of(&mut client, "components/page.html", 0, 24, bindings),
+        ["on-search", "query"]
+    );
+}
+
+#[test]
+fn completion_of_tag_names() {
+    let mut client = client();
+    client.open("components/page.html", "<div>\n  <us\n</div>\n");
+    // components and element directives, but not `my-tooltip`, an attribute
+    assert_eq!(
+        labels(&mut client, "components/page.html", 1, 5),
+        ["search-box", "user-card"]
+    );
+    assert_eq!(
+        labels(&mut client, "components/page.html", 1, 3),
+        ["search-box", "user-card"]
+    );
+    // not in closing tags nor in text
+    assert!(labels(&mut client, "components/page.html", 2, 5).is_empty());
+    assert!(labels(&mut client, "components/page.html", 0, 5).is_empty());
+}
+
+#[test]
+fn completion_of_attribute_names() {
+    let mut client = client();
+    client.open(
+        "components/page.html",
+        "<div ng-if=\"true\" my-tooltip ></div>\n",
+    );
+    let items = client.completion("components/page.html", 0, 29);
+    let labels: Vec<_> = items.iter().map(|item| item.label.as_str()).collect();
+    assert!(labels.contains(&"highlight"));
+    assert!(labels.contains(&"ng-repeat"));
+    // not those already set
+    assert!(!labels.contains(&"ng-if"));
+    assert!(!labels.contains(&"my-tooltip"));
+    let ng_show = items.iter().find(|item| item.label == "ng-show").unwrap();
+    assert_eq!(ng_show.kind, Some(CompletionItemKind::KEYWORD));
+    assert!(matches!(
+        &ng_show.documentation,
+        Some(Documentation::String(summary)) if summary.contains("truthy")
+    ));
+}
+
+#[test]
+fn diagnostics_of_bindings() {
+    let mut client = client();
+    client.open(
+        "components/page.html",
+        "<user-card data-on-save=\"x()\" bogus=\"1\" class=\"big\" ng-if=\"true\"></user-card>\n",
+    );
+    let diagnostics = client.diagnostics("components/page.html");
+    let codes: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.range.start.character, diagnostic.code.clone()))
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            (
+                30,
+                Some(NumberOrString::String("unknown-binding".to_owned()))
+            ),
+            (
+                1,
+                Some(NumberOrString::String("missing-binding".to_owned()))
+            ),
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "'user-card' is missing the binding 'user' of the component 'userCard'"
+    );
+}
+
+#[test]
+fn bindings_follow_scripts_changed_on_disk() {
+    let root = std::env::temp_dir().join(format!("myls-watched-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let script = root.join("badge.component.ts");
+    fs::write(
+        &script,
+        "angular.module('app').component('badgeCard', { bindings: { count: '<' } });\n",
+    )
+    .unwrap();
+    let mut client = TestClient::with_fixtures(&root);
+    client.initialize();
+    client.open("badge.html", "<badge-card tally=\"1\"></badge-card>\n");
+    let codes = |diagnostics: Vec<Diagnostic>| -> Vec<_> {
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| diagnostic.code)
+            .collect()
+    };
+    assert_eq!(
+        codes(client.diagnostics("badge.html")),
+        [
+            NumberOrString::String("unknown-binding".to_owned()),
+            NumberOrString::String("missing-binding".to_owned()),
+        ]
+    );
+
+    fs::write(
+        &script,
+        "angular.module('app').component('badgeCard', { bindings: { tally: '<' } });\n",
+    )
+    .unwrap();
+    client.notify::<notification::DidChangeWatchedFiles>(DidChangeWatchedFilesParams {
+        changes: vec![FileEvent::new(
+            client.uri("badge.component.ts"),
+            FileChangeType::CHANGED,
+        )],
+    });
+    let diagnostics = client.diagnostics("badge.html");
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(diagnostics, []);
+}
+
+#[test]
+fn bindings_follow_open_scripts() {
+    let mut client = client();
+    client.open("components/page.html", "<user-card ></user-card>\n");
+    let bindings = Some(CompletionItemKind::PROPERTY);
+    client.open(
+        "components/components.ts",
+        "angular.module('app').component('userCard', { bindings: { size: '<' } });\n",
+    );
+    assert_eq!(
+        labels_of(&mut client, "components/page.html", 0, 11, bindings),
+        ["size"]
+    );
+    client.notify::<notification::DidChangeTextDocument>(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier::new(
+            client.uri("components/components.ts"),
+            2,
+        ),
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "angular.module('app').component('userCard', { bindings: { tone: '@' } });\n"
+                .to_owned(),
+        }],
+    });
+    assert_eq!(
+        labels_of(&mut client, "components/page.html", 0, 11, bindings),
+        ["tone"]
+    );
+}
+
+#[test]
+fn aliased_bindings() {
+    let mut client = client();
+    client.open(
+        "components/avatar.component.ts",
+        "angular.module('app').component('userAvatar', {\n  bindings: { user: '<userData', size: '@?' },\n});\n",
+    );
+    client.open(
+        "components/page.html",
+        "<user-avatar user-data=\"1\" ></user-avatar>\n<user-avatar user=\"1\"></user-avatar>\n",
+    );
+    let diagnostics = client.diagnostics("components/page.html");
+    let codes: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.range.start, diagnostic.code.clone()))
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            (
+                Position::new(1, 13),
+                Some(NumberOrString::String("unknown-binding".to_owned()))
+            ),
+            (
+                Position::new(1, 1),
+                Some(NumberOrString::String("missing-binding".to_owned()))
+            ),
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "'user-avatar' is missing the binding 'user-data' of the component 'userAvatar'"
+    );
+
+    let items: Vec<_> = client
+        .completion("components/page.html", 0, 27)
+        .into_iter()
+        .filter(|item| item.kind == Some(CompletionItemKind::PROPERTY))
+        .map(|item| (item.label, item.detail.unwrap_or_default()))
+        .collect();
+    assert_eq!(items, [("size".to_owned(), "size: '@?'".to_owned())]);
+
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.definition("components/page.html", 0, 15)
+    else {
+        panic!("expected the aliased binding");
+    };
+    assert_eq!(location.uri, client.uri("components/avatar.component.ts"));
+    assert_eq!(
+        location.range,
+        Range::new(Position::new(1, 14), Position::new(1, 18))
+    );
+}
+
+#[test]
+fn only_expression_bindings_reference_the_controller() {
+    let mut client = client();
+    client.open(
+        "components/page.html",
+        "<div ng-controller=\"ProfileBadgeController as badge\"><user-card user=\"badge.name\" \
+         on-save=\"badge.nope()\" title=\"badge.title\" class=\"badge.big\"></user-card></div>\n",
+    );
+    let diagnostics = client.diagnostics("components/page.html");
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code.clone(), diagnostic.range.start))
+        .collect();
+    assert_eq!(
+        found,
+        [(
+            Some(NumberOrString::String("unknown-member".to_owned())),
+            Position::new(0, 97)
+        )]
+    );
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use ls_core::{
+    Config, Workspace,
+    config::Severity,
+    protocol::{
+        ClientCapabilities, InitializeParams, NumberOrString, Position, Uri,
+        WorkspaceClientCapabilities, WorkspaceFolder, notification::PublishDiagnostics,
+    },
+};
+use serde_json::json;
+
+mod common;
+
+use common::{FIXTURES, fixtures, uri};
+
+/// `test.html` with `vm.testVar === 0` swapped for `expression`
+fn template(expression: &str) -> String {
+    std::fs::read_to_string(Path::new(FIXTURES).join("test.html"))
+        .unwrap()
+        .replace("vm.testVar === 0", expression)
+}
+
+#[test]
+fn layers_override_the_defaults() {
+    let config = Config::from_layers([
+        &json!({ "controllerAliases": ["$ctrl"], "diagnostics": { "unknown-member": "hint" } }),
+        &json!({ "diagnostics": { "unknown-directive": "off" }, "exclude": null }),
+    ])
+    .unwrap();
+    assert_eq!(config.controller_aliases, ["$ctrl"]);
+    assert_eq!(
+        config.controller_suffixes,
+        Config::default().controller_suffixes
+    );
+    assert_eq!(config.exclude, Config::default().exclude);
+    assert_eq!(config.diagnostics["unknown-member"], Severity::Hint);
+    assert_eq!(config.diagnostics["unknown-directive"], Severity::Off);
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    assert!(Config::from_layers([&json!({ "controllerAliases": "vm" })]).is_err());
+    assert!(Config::from_layers([&json!({ "include": ["a/{b"] })]).is_err());
+}
+
+#[test]
+fn aliases_and_interpolation_symbols() {
+    let mut workspace = Workspace::with_root(Path::new(FIXTURES).canonicalize().unwrap());
+    workspace
+        .set_config(
+            Config::from_layers([&json!({
+                "controllerAliases": ["$ctrl"],
+                "interpolation": { "start": "[[", "end": "]]" },
+            })])
+            .unwrap(),
+        )
+        .unwrap();
+    workspace
+        .open(
+            &uri("test.html"),
+            "<p>[[ $ctrl.testValue ]] {{ vm.nope }}</p>\n",
+        )
+        .unwrap();
+    assert!(
+        workspace
+            .resolve_definition(&uri("test.html"), Position::new(0, 13))
+            .is_some()
+    );
+    assert!(workspace.template_diagnostics(&uri("test.html")).is_empty());
+}
+
+#[test]
+fn excluded_files_are_not_templates() {
+    let mut workspace = Workspace::with_root(Path::new(FIXTURES).canonicalize().unwrap());
+    workspace
+        .set_config(Config::from_layers([&json!({ "exclude": ["test.html"] })]).unwrap())
+        .unwrap();
+    assert_eq!(workspace.diagnostics().count(), 0);
+}
+
+#[test]
+fn initialization_options_set_severities() {
+    let mut client = fixtures();
+    client.initialize_with(InitializeParams {
+        initialization_options: Some(json!({ "diagnostics": { "unknown-member": "off" } })),
+        ..Default::default()
+    });
+    client.open("test.html", &template("vm.nope"));
+    assert!(client.diagnostics("test.html").is_empty());
+}
+
+#[test]
+fn settings_are_pulled_from_the_client() {
+    let mut client = fixtures();
+    client.initialize_with(InitializeParams {
+        capabilities: ClientCapabilities {
+            workspace: Some(WorkspaceClientCapabilities {
+                configuration: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let request = client.receive();
+    assert_eq!(request["method"], "workspace/configuration");
+    assert_eq!(request["params"]["items"][0]["section"], "myls");
+    client.open("test.html", &template("vm.nope"));
+    assert_eq!(client.diagnostics("test.html").len(), 1);
+
+    client.send_raw(
+        &json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": [{ "diagnostics": { "unknown-member": "off" } }],
+        })
+        .to_string(),
+    );
+    // the open template is checked again with the new settings
+    assert!(client.diagnostics("test.html").is_empty());
+}
+
+#[test]
+fn excluded_scripts_are_not_looked_into() {
+    let mut workspace = Workspace::with_root(Path::new(FIXTURES).canonicalize().unwrap());
+    let page = uri("test.html")
+        .as_str()
+        .replace("test.html", "filters/page.html");
+    let page: Uri = page.parse().unwrap();
+    workspace
+        .open(&page, "<p>{{ 'Ada Lovelace' | initials }}</p>\n")
+        .unwrap();
+    assert!(workspace.template_diagnostics(&page).is_empty());
+    workspace
+        .set_config(Config::from_layers([&json!({ "exclude": ["filters/*.js"] })]).unwrap())
+        .unwrap();
+    let codes: Vec<_> = workspace
+        .template_diagnostics(&page)
+        .into_iter()
+        .filter_map(|diagnostic| diagnostic.code)
+        .collect();
+    assert_eq!(codes, [NumberOrString::String("unknown-filter".to_owned())]);
+}
+
+#[test]
+fn excluded_scripts_get_no_diagnostics() {
+    let mut client = fixtures();
+    let folder = WorkspaceFolder {
+        uri: client.uri("inline"),
+        name: "inline".to_owned(),
+    };
+    client.initialize_with(InitializeParams {
+        initialization_options: Some(json!({ "exclude": ["dist/**"] })),
+        workspace_folders: Some(vec![folder]),
+        ..Default::default()
+    });
+    let script = "angular.module('app').component('badgeCard', {\n  template: '<b>{{ $ctrl.count }}</b>',\n});\n";
+    client.open("inline/dist/badge.js", script);
+    client.open("inline/badge.js", script);
+    // the first script opened was skipped
+    let published = client.notification::<PublishDiagnostics>();
+    assert_eq!(published.uri, client.uri("inline/badge.js"));
+    assert_eq!(published.diagnostics.len(), 1);
+}
@@ -0,0 +1,145 @@
+use ls_core::{
+    protocol::{
+        NumberOrString, Position, ReferenceContext, ReferenceParams, TextDocumentIdentifier,
+        TextDocumentPositionParams, request,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::definition;
+
+/// `OrderController` is registered as `OrderCtrl`, `LineController` isn't
+/// registered and goes by its class name
+const PAGE: &str = r#"<div ng-controller="OrderCtrl as order">
+  <p>{{ order.total }}</p>
+  <div ng-controller="LineController as line">
+    <span>{{ line.quantity }} {{ order.submit() }} {{ line.missing }}</span>
+  </div>
+</div>
+<p>{{ order.missing }}</p>
+"#;
+
+fn client() -> TestClient {
+    let mut client = common::client();
+    client.open("orders/page.html", PAGE);
+    client
+}
+
+#[test]
+fn aliases_resolve_to_their_controller() {
+    let mut client = client();
+    assert_eq!(
+        definition(&mut client, "orders/page.html", 1, 14),
+        Some((client.uri("orders/OrderController.ts"), Position::new(1, 2)))
+    );
+    assert_eq!(
+        definition(&mut client, "orders/page.html", 3, 18),
+        Some((client.uri("orders/LineController.ts"), Position::new(1, 2)))
+    );
+    // the outer alias is still reachable from the nested element
+    assert_eq!(
+        definition(&mut client, "orders/page.html", 3, 41),
+        Some((client.uri("orders/OrderController.ts"), Position::new(4, 2)))
+    );
+    // but not outside its element
+    assert_eq!(definition(&mut client, "orders/page.html", 6, 12), None);
+}
+
+#[test]
+fn definition_of_controller_name() {
+    let mut client = client();
+    assert_eq!(
+        definition(&mut client, "orders/page.html", 0, 22),
+        Some((client.uri("orders/orders.js"), Position::new(0, 34)))
+    );
+    assert_eq!(
+        definition(&mut client, "orders/page.html", 2, 25),
+        Some((client.uri("orders/LineController.ts"), Position::new(0, 13)))
+    );
+}
+
+#[test]
+fn members_are_checked_against_their_controller() {
+    let mut client = client();
+    let diagnostics = client.diagnostics("orders/page.html");
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code.clone(), diagnostic.range.start))
+        .collect();
+    let unknown_member = Some(NumberOrString::String("unknown-member".to_owned()));
+    assert_eq!(found, [(unknown_member, Position::new(3, 59))]);
+
+    client.open(
+        "orders/page.html",
+        "<div ng-controller=\"NopeController as nope\">{{ nope.x }}</div>\n",
+    );
+    let diagnostics = client.diagnostics("orders/page.html");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].code,
+        Some(NumberOrString::String("unresolved-controller".to_owned()))
+    );
+    assert_eq!(diagnostics[0].range.start, Position::new(0, 20));
+}
+
+#[test]
+fn references_inside_ng_controller() {
+    let mut client = client();
+    let references = client
+        .expect::<request::References>(ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: client.uri("orders/OrderController.ts"),
+                },
+                position: Position::new(1, 4),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        })
+        .unwrap_or_default();
+    let found: Vec<_> = references
+        .iter()
+        .map(|location| (location.uri.clone(), location.range.start))
+        .collect();
+    assert_eq!(
+        found,
+        [(client.uri("orders/page.html"), Position::new(1, 14))]
+    );
+}
+
+#[test]
+fn members_assigned_through_an_alias_of_this() {
+    let mut client = common::client();
+    client.open(
+        "orders/cart.js",
+        r#"angular.module('orders').controller('CartCtrl', function () {
+  var vm = this;
+  vm.items = [];
+  vm.clear = function () {};
+  var other = {};
+  other.hidden = 1;
+});
+"#,
+    );
+    client.open(
+        "orders/cart.html",
+        "<div ng-controller=\"CartCtrl as cart\">{{ cart.items }} {{ cart.clear() }} {{ cart.hidden }}</div>\n",
+    );
+    assert_eq!(
+        definition(&mut client, "orders/cart.html", 0, 46),
+        Some((client.uri("orders/cart.js"), Position::new(2, 5)))
+    );
+    let diagnostics = client.diagnostics("orders/cart.html");
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code.clone(), diagnostic.range.start))
+        .collect();
+    // `other` isn't the controller
+    let unknown_member = Some(NumberOrString::String("unknown-member".to_owned()));
+    assert_eq!(found, [(unknown_member, Position::new(0, 82))]);
+}
@@ -0,0 +1,91 @@
+use ls_core::{
+    protocol::{GotoDefinitionResponse, HoverContents, NumberOrString, Position, Range},
+    testing::TestClient,
+};
+
+mod common;
+
+use common::client;
+
+/// The line the definition at `line`, `character` of `test.html` is on in
+/// `TestController.ts`
+fn definition_line(client: &mut TestClient, line: u32, character: u32) -> Option<u32> {
+    match client.definition("test.html", line, character)? {
+        GotoDefinitionResponse::Scalar(location) => Some(location.range.start.line),
+        _ => panic!("expected a single location"),
+    }
+}
+
+#[test]
+fn expressions_in_micro_syntax() {
+    let mut client = client();
+    client.open(
+        "test.html",
+        concat!(
+            "<li ng-repeat=\"(key, item) in vm.testValue | orderBy:'name' as shown track by vm.testMethod(item)\"></li>\n",
+            "<select ng-options=\"item.name for item in vm.testValue track by item.id\"></select>\n",
+        ),
+    );
+    assert_eq!(definition_line(&mut client, 0, 33), Some(2));
+    assert_eq!(definition_line(&mut client, 0, 84), Some(4));
+    assert_eq!(definition_line(&mut client, 1, 45), Some(2));
+}
+
+#[test]
+fn plain_text_values_are_not_expressions() {
+    let mut client = client();
+    client.open(
+        "test.html",
+        "<div ng-app=\"vm.testValue\" ng-href=\"/{{ vm.testValue }}\"></div>\n",
+    );
+    assert_eq!(definition_line(&mut client, 0, 17), None);
+    assert_eq!(definition_line(&mut client, 0, 44), Some(2));
+}
+
+#[test]
+fn hover_on_directive_name() {
+    let mut client = client();
+    client.open("test.html", "<p data-ng-if=\"vm.testValue\"></p>\n");
+    let hover = client.hover("test.html", 0, 6).expect("a hover");
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert!(contents.value.starts_with("**ng-if**"));
+    assert!(contents.value.contains("Value: an expression"));
+    assert_eq!(
+        hover.range,
+        Some(Range::new(Position::new(0, 3), Position::new(0, 13)))
+    );
+    // not on attributes that aren't built-in directives
+    client.open("test.html", "<p title=\"x\"></p>\n");
+    assert!(client.hover("test.html", 0, 5).is_none());
+}
+
+#[test]
+fn ng_directives_of_the_project_and_of_angular_modules_are_known() {
+    let mut client = client();
+    client.open(
+        "components/enter.directive.js",
+        "angular.module('app').directive('ngEnter', () => ({ restrict: 'A' }));\n",
+    );
+    client.open(
+        "components/page.html",
+        concat!(
+            "<input ng-enter=\"save()\" ng-frobnicate>\n",
+            "<div ng-view></div>\n",
+            "<div ng-messages=\"form.email.$error\"><p ng-message=\"required\"></p></div>\n",
+        ),
+    );
+    let diagnostics: Vec<_> = client
+        .diagnostics("components/page.html")
+        .into_iter()
+        .map(|diagnostic| (diagnostic.range.start, diagnostic.code))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        [(
+            Position::new(0, 25),
+            Some(NumberOrString::String("unknown-directive".to_owned()))
+        )]
+    );
+}
@@ -0,0 +1,88 @@
+use ls_core::{
+    protocol::{GotoDefinitionResponse, HoverContents, Location, NumberOrString, Position, Range},
+    testing::TestClient,
+};
+
+mod common;
+
+use common::client;
+
+fn hover_text(client: &mut TestClient, line: u32, character: u32) -> Option<String> {
+    let hover = client.hover("filters/page.html", line, character)?;
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup");
+    };
+    Some(contents.value)
+}
+
+#[test]
+fn definition_of_filter() {
+    let mut client = client();
+    client.open(
+        "filters/page.html",
+        "<p>{{ vm.name | initials:'.' }}</p>\n<p>{{ vm.bio | truncate }}</p>\n",
+    );
+    let expected = GotoDefinitionResponse::Scalar(Location::new(
+        client.uri("filters/filters.js"),
+        Range::new(Position::new(2, 11), Position::new(2, 19)),
+    ));
+    assert_eq!(
+        client.definition("filters/page.html", 0, 18),
+        Some(expected)
+    );
+    assert!(client.definition("filters/page.html", 1, 16).is_some());
+}
+
+#[test]
+fn completion_after_pipe() {
+    let mut client = client();
+    client.open(
+        "filters/page.html",
+        "<p>{{ vm.price | }}</p>\n<p>{{ vm.a || vm.b }}</p>\n",
+    );
+    let labels: Vec<_> = client
+        .completion("filters/page.html", 0, 16)
+        .into_iter()
+        .map(|item| item.label)
+        .collect();
+    assert!(labels.contains(&"currency".to_owned()));
+    assert!(labels.contains(&"initials".to_owned()));
+    assert!(labels.contains(&"truncate".to_owned()));
+    assert!(client.completion("filters/page.html", 1, 13).is_empty());
+}
+
+#[test]
+fn hover_shows_parameters() {
+    let mut client = client();
+    client.open(
+        "filters/page.html",
+        "<p>{{ vm.price | currency:'EUR' }} {{ vm.name | initials }} {{ vm.bio | truncate }}</p>\n",
+    );
+    let currency = hover_text(&mut client, 0, 19).expect("a hover on `currency`");
+    assert!(currency.contains("amount | currency:symbol:fractionSize"));
+    let initials = hover_text(&mut client, 0, 50).expect("a hover on `initials`");
+    assert!(initials.contains("name | initials:separator"));
+    let truncate = hover_text(&mut client, 0, 75).expect("a hover on `truncate`");
+    assert!(truncate.contains("text | truncate:length"));
+}
+
+#[test]
+fn unknown_filter_is_reported() {
+    let mut client = client();
+    client.open(
+        "filters/page.html",
+        "<li ng-repeat=\"user in vm.users | orderBy:'name' | sparkle\">{{ user | initials }}</li>\n",
+    );
+    // `vm` has no controller here, which is reported too
+    let unknown_filter = Some(NumberOrString::String("unknown-filter".to_owned()));
+    let diagnostics: Vec<_> = client
+        .diagnostics("filters/page.html")
+        .into_iter()
+        .filter(|diagnostic| diagnostic.code == unknown_filter)
+        .collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].range,
+        Range::new(Position::new(0, 51), Position::new(0, 58))
+    );
+}
@@ -0,0 +1,75 @@
+use ls_core::{
+    protocol::{
+        DidChangeWorkspaceFoldersParams, GotoDefinitionResponse, InitializeParams, WorkspaceFolder,
+        WorkspaceFoldersChangeEvent, notification,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::fixtures;
+
+/// Two apps of a monorepo with a `page.html` and `PageController.ts` each.
+/// `app-b` calls its controller `$ctrl` in its `.mylsrc.json`
+fn folder(client: &TestClient, name: &str) -> WorkspaceFolder {
+    WorkspaceFolder {
+        uri: client.uri(&format!("monorepo/{name}")),
+        name: name.to_owned(),
+    }
+}
+
+fn client(folders: &[&str]) -> TestClient {
+    let mut client = fixtures();
+    let folders = folders.iter().map(|name| folder(&client, name)).collect();
+    client.initialize_with(InitializeParams {
+        workspace_folders: Some(folders),
+        ..Default::default()
+    });
+    client
+}
+
+#[test]
+fn controllers_resolve_within_their_folder() {
+    let mut client = client(&["app-a", "app-b"]);
+    client.open_fixture("monorepo/app-a/page.html");
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.definition("monorepo/app-a/page.html", 1, 12)
+    else {
+        panic!("no definition for `vm.title`");
+    };
+    assert_eq!(location.uri, client.uri("monorepo/app-a/PageController.ts"));
+}
+
+#[test]
+fn each_folder_has_its_own_config() {
+    let mut client = client(&["app-a", "app-b"]);
+    client.open("monorepo/app-b/page.html", "<p>{{ $ctrl.missing }}</p>\n");
+    assert_eq!(client.diagnostics("monorepo/app-b/page.html").len(), 1);
+    client.open("monorepo/app-a/page.html", "<p>{{ $ctrl.missing }}</p>\n");
+    assert!(client.diagnostics("monorepo/app-a/page.html").is_empty());
+}
+
+#[test]
+fn added_folders_take_over_their_open_documents() {
+    let mut client = client(&["app-a"]);
+    client.open("monorepo/app-b/page.html", "<p>{{ $ctrl.missing }}</p>\n");
+    assert!(client.diagnostics("monorepo/app-b/page.html").is_empty());
+
+    let added = folder(&client, "app-b");
+    client.notify::<notification::DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![added.clone()],
+            removed: vec![],
+        },
+    });
+    assert_eq!(client.diagnostics("monorepo/app-b/page.html").len(), 1);
+
+    client.notify::<notification::DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![],
+            removed: vec![added],
+        },
+    });
+    assert!(client.diagnostics("monorepo/app-b/page.html").is_empty());
+}
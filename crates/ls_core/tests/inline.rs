@@ -0,0 +1,133 @@
+use ls_core::{
+    protocol::{
+        HoverContents, NumberOrString, Position, Range, ReferenceContext, ReferenceParams,
+        TextDocumentIdentifier, TextDocumentPositionParams, request,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::definition;
+
+/// `inlineOrders` has a controller, `inlineBadge` only its bindings
+const SCRIPT: &str = "inline/inline.component.ts";
+
+fn client() -> TestClient {
+    let mut client = common::client();
+    client.open_fixture(SCRIPT);
+    client
+}
+
+#[test]
+fn definition_in_inline_template() {
+    let mut client = client();
+    let script = client.uri(SCRIPT);
+    assert_eq!(
+        definition(&mut client, SCRIPT, 11, 45),
+        Some((script.clone(), Position::new(1, 2)))
+    );
+    assert_eq!(
+        definition(&mut client, SCRIPT, 11, 26),
+        Some((script.clone(), Position::new(2, 2)))
+    );
+    // `orders` is a binding of the component
+    assert_eq!(
+        definition(&mut client, SCRIPT, 12, 35),
+        Some((script.clone(), Position::new(7, 4)))
+    );
+    // outside the template, the script isn't HTML
+    assert_eq!(definition(&mut client, SCRIPT, 9, 16), None);
+}
+
+#[test]
+fn hover_and_completion_in_inline_template() {
+    let mut client = client();
+    let hover = client.hover(SCRIPT, 11, 10).expect("a hover");
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert!(contents.value.starts_with("**ng-click**"));
+    assert_eq!(
+        hover.range,
+        Some(Range::new(Position::new(11, 8), Position::new(11, 16)))
+    );
+    let labels: Vec<_> = client
+        .completion(SCRIPT, 12, 57)
+        .into_iter()
+        .map(|item| item.label)
+        .collect();
+    assert!(labels.contains(&"currency".to_owned()));
+    assert!(client.completion(SCRIPT, 1, 2).is_empty());
+    assert!(client.hover(SCRIPT, 1, 2).is_none());
+}
+
+#[test]
+fn diagnostics_at_script_positions() {
+    let mut client = client();
+    assert!(client.diagnostics(SCRIPT).is_empty());
+    let text = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../test/inline/inline.component.ts"
+    ))
+    .unwrap()
+    .replace("{{$ctrl.title}}", "{{$ctrl.titel}}")
+    // what comes before the template on its line is counted in UTF-16
+    .replace(
+        "  template: '<span class=\"badge\">{{$ctrl.count}}</span>',",
+        "  template: /* ü */ '<span>{{$ctrl.counts}}</span>',",
+    );
+    client.open(SCRIPT, &text);
+    let diagnostics: Vec<_> = client
+        .diagnostics(SCRIPT)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.range, diagnostic.message))
+        .collect();
+    let code = Some(NumberOrString::String("unknown-member".to_owned()));
+    assert_eq!(
+        diagnostics,
+        [
+            (
+                code.clone(),
+                Range::new(Position::new(11, 43), Position::new(11, 48)),
+                "'titel' doesn't exist on the controller 'inline.component.ts'".to_owned()
+            ),
+            (
+                code,
+                Range::new(Position::new(18, 35), Position::new(18, 41)),
+                "'counts' isn't a binding of the component".to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn references_in_inline_template() {
+    let mut client = client();
+    let references = client
+        .expect::<request::References>(ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: client.uri(SCRIPT),
+                },
+                position: Position::new(1, 4),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        })
+        .unwrap_or_default();
+    let found: Vec<_> = references
+        .iter()
+        .map(|location| (location.uri.clone(), location.range))
+        .collect();
+    assert_eq!(
+        found,
+        [(
+            client.uri(SCRIPT),
+            Range::new(Position::new(11, 43), Position::new(11, 48))
+        )]
+    );
+}
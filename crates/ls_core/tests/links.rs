@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use ls_core::{
+    protocol::{
+        DocumentLinkParams, GotoDefinitionResponse, InitializeParams, Location, Position, Range,
+        TextDocumentIdentifier, WorkspaceFolder, myls, request,
+    },
+    testing::TestClient,
+};
+use serde_json::json;
+
+mod common;
+
+use common::FIXTURES;
+
+const SCRIPT: &str = "links/src/app/orders/orders.component.ts";
+
+/// `links` resolves `templateUrl`s from its `src` directory
+fn client() -> TestClient {
+    let mut client = TestClient::with_fixtures(Path::new(FIXTURES).canonicalize().unwrap());
+    let folder = WorkspaceFolder {
+        uri: client.uri("links"),
+        name: "links".to_owned(),
+    };
+    client.initialize_with(InitializeParams {
+        workspace_folders: Some(vec![folder]),
+        initialization_options: Some(json!({ "templateBasePaths": ["src"] })),
+        ..Default::default()
+    });
+    client
+}
+
+fn document(client: &TestClient, path: &str) -> TextDocumentIdentifier {
+    TextDocumentIdentifier::new(client.uri(path))
+}
+
+fn start_of(client: &TestClient, path: &str) -> Location {
+    Location::new(client.uri(path), Range::default())
+}
+
+#[test]
+fn template_urls_link_to_templates() {
+    let mut client = client();
+    let links = client
+        .expect::<request::DocumentLinkRequest>(DocumentLinkParams {
+            text_document: document(&client, SCRIPT),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .unwrap();
+    let links: Vec<_> = links
+        .into_iter()
+        .map(|link| (link.range.start, link.target.unwrap()))
+        .collect();
+    // `archive.html` doesn't exist
+    assert_eq!(
+        links,
+        vec![
+            (
+                Position::new(5, 16),
+                client.uri("links/src/app/orders/orders.html")
+            ),
+            (
+                Position::new(10, 16),
+                client.uri("links/src/app/orders/orders-list.html")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn definition_of_template_url() {
+    let mut client = client();
+    client.open_fixture(SCRIPT);
+    assert_eq!(
+        client.definition(SCRIPT, 5, 20),
+        Some(GotoDefinitionResponse::Scalar(start_of(
+            &client,
+            "links/src/app/orders/orders.html"
+        )))
+    );
+    assert_eq!(client.definition(SCRIPT, 14, 20), None);
+    // nothing in scripts but their `templateUrl`s
+    assert_eq!(client.definition(SCRIPT, 6, 20), None);
+    assert!(client.completion(SCRIPT, 0, 0).is_empty());
+}
+
+#[test]
+fn toggles_between_template_url_and_script() {
+    let mut client = client();
+    let template = client.expect::<myls::GoToTemplate>(document(&client, SCRIPT));
+    assert_eq!(
+        template,
+        Some(start_of(&client, "links/src/app/orders/orders.html"))
+    );
+    let controller = client
+        .expect::<myls::GoToController>(document(&client, "links/src/app/orders/orders-list.html"));
+    assert_eq!(controller, Some(start_of(&client, SCRIPT)));
+}
+
+#[test]
+fn toggles_between_template_and_controller_by_file_name() {
+    let mut client = client();
+    let template = client.expect::<myls::GoToTemplate>(document(
+        &client,
+        "links/src/app/profile/ProfileController.ts",
+    ));
+    assert_eq!(
+        template,
+        Some(start_of(&client, "links/src/app/profile/profile.html"))
+    );
+    let controller = client
+        .expect::<myls::GoToController>(document(&client, "links/src/app/profile/profile.html"));
+    assert_eq!(
+        controller,
+        Some(start_of(
+            &client,
+            "links/src/app/profile/ProfileController.ts"
+        ))
+    );
+}
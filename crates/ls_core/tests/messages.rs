@@ -0,0 +1,200 @@
+use ls_core::{
+    protocol::{
+        CancelParams, HoverParams, NumberOrString, Position, Range, ReferenceContext,
+        ReferenceParams, SetTraceParams, TextDocumentIdentifier, TextDocumentPositionParams,
+        TraceValue,
+        notification::{Cancel, LogTrace, SetTrace},
+        request::{HoverRequest, References, Shutdown},
+    },
+    testing::TestClient,
+};
+use serde_json::{Value, json};
+
+mod common;
+
+use common::{client, fixtures};
+
+/// The next response from the server, skipping notifications
+fn response(client: &mut TestClient) -> Value {
+    loop {
+        let message = client.receive();
+        if message.get("method").is_none() {
+            return message;
+        }
+    }
+}
+
+/// Params of a hover on `ng-if` in the templates of these tests, at `path`
+fn hover_params(client: &TestClient, path: &str) -> HoverParams {
+    HoverParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(client.uri(path)),
+            Position::new(0, 4),
+        ),
+        work_done_progress_params: Default::default(),
+    }
+}
+
+#[test]
+fn failed_requests_get_an_error_and_the_server_keeps_going() {
+    let mut client = client();
+    client.open("test.html", "<p ng-if=\"true\"></p>\n");
+    client.send_raw(
+        &json!({
+            "jsonrpc": "2.0",
+            "id": "bad-hover",
+            "method": "textDocument/hover",
+            "params": { "position": "nowhere" },
+        })
+        .to_string(),
+    );
+    let reply = response(&mut client);
+    assert_eq!(reply["id"], "bad-hover");
+    assert_eq!(reply["error"]["code"], -32602);
+    client.send_raw(
+        &json!({ "jsonrpc": "2.0", "id": "unknown", "method": "myls/unknown" }).to_string(),
+    );
+    let reply = response(&mut client);
+    assert_eq!(reply["id"], "unknown");
+    assert_eq!(reply["error"]["code"], -32601);
+    assert_eq!(reply["error"]["data"], json!({ "method": "myls/unknown" }));
+    // later requests are still answered
+    let hover = client.hover("test.html", 0, 4).expect("a hover");
+    assert_eq!(
+        hover.range,
+        Some(Range::new(Position::new(0, 3), Position::new(0, 8)))
+    );
+    client.shutdown();
+}
+
+#[test]
+fn requests_are_traced_as_the_client_asks() {
+    let mut client = client();
+    client.open("test.html", "<p ng-if=\"true\"></p>\n");
+    client.notify::<SetTrace>(SetTraceParams {
+        value: TraceValue::Verbose,
+    });
+    client.hover("test.html", 0, 4);
+    let received = client.notification::<LogTrace>();
+    assert!(
+        received
+            .message
+            .starts_with("Received request 'textDocument/hover")
+    );
+    assert!(
+        received
+            .verbose
+            .is_some_and(|params| params.contains("test.html"))
+    );
+    let sent = client.notification::<LogTrace>();
+    assert!(
+        sent.message
+            .starts_with("Sending response 'textDocument/hover")
+    );
+    // only the messages themselves once verbose traces are turned off
+    client.notify::<SetTrace>(SetTraceParams {
+        value: TraceValue::Messages,
+    });
+    client.hover("test.html", 0, 4);
+    // the `$/setTrace` itself was still traced verbosely
+    let set_trace = client.notification::<LogTrace>();
+    assert!(
+        set_trace
+            .message
+            .starts_with("Received notification '$/setTrace'")
+    );
+    let received = client.notification::<LogTrace>();
+    assert!(
+        received
+            .message
+            .starts_with("Received request 'textDocument/hover")
+    );
+    assert_eq!(received.verbose, None);
+    client.shutdown();
+}
+
+#[test]
+fn requests_before_initialize_are_refused() {
+    let mut client = fixtures();
+    let err = client
+        .request::<HoverRequest>(hover_params(&client, "test.html"))
+        .unwrap_err();
+    assert_eq!(err.code, -32002);
+    client.initialize();
+    client.open("test.html", "<p ng-if=\"true\"></p>\n");
+    assert!(client.hover("test.html", 0, 4).is_some());
+}
+
+#[test]
+fn requests_after_shutdown_are_invalid() {
+    let mut client = client();
+    client.expect::<Shutdown>(());
+    let err = client
+        .request::<HoverRequest>(hover_params(&client, "test.html"))
+        .unwrap_err();
+    assert_eq!(err.code, -32600);
+}
+
+#[test]
+fn cancelled_requests_are_not_handled() {
+    let mut client = client();
+    client.open("test.html", "<p ng-if=\"true\"></p>\n");
+    let params = hover_params(&client, "test.html");
+    client.send_together(&[
+        json!({ "jsonrpc": "2.0", "id": "hover", "method": "textDocument/hover", "params": params }),
+        json!({ "jsonrpc": "2.0", "method": "$/cancelRequest", "params": { "id": "hover" } }),
+    ]);
+    let reply = response(&mut client);
+    assert_eq!(reply["id"], "hover");
+    assert_eq!(reply["error"]["code"], -32800);
+    // cancelling a request that was answered already does nothing
+    client.notify::<Cancel>(CancelParams {
+        id: NumberOrString::String("hover".to_owned()),
+    });
+    assert!(client.hover("test.html", 0, 4).is_some());
+}
+
+#[test]
+fn requests_on_documents_changed_since_are_not_handled() {
+    let mut client = client();
+    client.open("test.html", "<p ng-if=\"true\"></p>\n");
+    let params = hover_params(&client, "test.html");
+    let uri = client.uri("test.html");
+    client.send_together(&[
+        json!({ "jsonrpc": "2.0", "id": "hover", "method": "textDocument/hover", "params": params }),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": "<p ng-show=\"true\"></p>\n" }],
+            },
+        }),
+    ]);
+    let reply = response(&mut client);
+    assert_eq!(reply["id"], "hover");
+    assert_eq!(reply["error"]["code"], -32801);
+}
+
+#[test]
+fn unreadable_documents_fail_the_request() {
+    let mut client = client();
+    let err = client
+        .request::<References>(ReferenceParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(client.uri("missing.html")),
+                Position::new(0, 0),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        })
+        .unwrap_err();
+    assert_eq!(err.code, -32803);
+    assert_eq!(
+        err.data,
+        Some(json!({ "uri": client.uri("missing.html").as_str() }))
+    );
+}
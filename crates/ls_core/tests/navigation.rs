@@ -0,0 +1,196 @@
+use ls_core::{
+    protocol::{
+        DiagnosticSeverity, GotoDefinitionResponse, HoverContents, NumberOrString, OneOf, Position,
+        Range,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::{client, fixtures};
+
+/// `test.html` with `vm.testVar` swapped for `expression`
+fn template(expression: &str) -> String {
+    std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../../test/test.html"))
+        .unwrap()
+        .replace("vm.testVar === 0", expression)
+}
+
+#[test]
+fn initialize_advertises_navigation() {
+    let mut client = fixtures();
+    let result = client.initialize();
+    assert_eq!(
+        result.capabilities.definition_provider,
+        Some(OneOf::Left(true))
+    );
+    assert!(result.capabilities.hover_provider.is_some());
+    client.shutdown();
+}
+
+#[test]
+fn definition_of_field() {
+    let mut client = client();
+    client.open("test.html", &template("vm.testValue === 0"));
+    let definition = client.definition("test.html", 4, 20);
+    let expected = GotoDefinitionResponse::Scalar(ls_core::protocol::Location::new(
+        client.uri("TestController.ts"),
+        Range::new(Position::new(2, 2), Position::new(2, 11)),
+    ));
+    assert_eq!(definition, Some(expected));
+}
+
+#[test]
+fn definition_of_method() {
+    let mut client = client();
+    client.open("test.html", &template("vm.testMethod()"));
+    let Some(GotoDefinitionResponse::Scalar(location)) = client.definition("test.html", 4, 17)
+    else {
+        panic!("expected a single location");
+    };
+    assert_eq!(location.uri, client.uri("TestController.ts"));
+    assert_eq!(location.range.start, Position::new(4, 2));
+}
+
+#[test]
+fn definition_of_unknown_member() {
+    let mut client = client();
+    client.open_fixture("test.html");
+    assert_eq!(client.definition("test.html", 4, 17), None);
+}
+
+#[test]
+fn definition_outside_expressions() {
+    let mut client = client();
+    client.open_fixture("test.html");
+    assert_eq!(client.definition("test.html", 2, 10), None);
+}
+
+#[test]
+fn hover_shows_declaration() {
+    let mut client = client();
+    client.open("test.html", &template("vm.testValue === 0"));
+    let hover = client.hover("test.html", 4, 17).expect("a hover");
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert!(contents.value.contains("testValue: number;"));
+    assert_eq!(
+        hover.range,
+        Some(Range::new(Position::new(4, 17), Position::new(4, 26)))
+    );
+}
+
+#[test]
+fn unknown_member_is_reported() {
+    let mut client = client();
+    client.open_fixture("test.html");
+    let diagnostics = client.diagnostics("test.html");
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(
+        diagnostic.code,
+        Some(NumberOrString::String("unknown-member".to_owned()))
+    );
+    assert_eq!(
+        diagnostic.range,
+        Range::new(Position::new(4, 17), Position::new(4, 24))
+    );
+}
+
+#[test]
+fn invalid_json_gets_a_parse_error() {
+    let mut client = client();
+    client.send_raw("{not json");
+    let reply = client.receive();
+    assert_eq!(reply["error"]["code"], -32700);
+    assert_eq!(reply["id"], serde_json::Value::Null);
+}
+
+#[test]
+fn definition_of_component_tag() {
+    let mut client = client();
+    client.open_fixture("components/user-list.html");
+    let expected = GotoDefinitionResponse::Scalar(ls_core::protocol::Location::new(
+        client.uri("components/components.ts"),
+        Range::new(Position::new(0, 33), Position::new(0, 41)),
+    ));
+    // on the start and the end tag
+    assert_eq!(
+        client.definition("components/user-list.html", 1, 10),
+        Some(expected.clone())
+    );
+    assert_eq!(
+        client.definition("components/user-list.html", 1, 33),
+        Some(expected)
+    );
+}
+
+#[test]
+fn definition_of_component_tag_without_registration() {
+    let mut client = client();
+    client.open_fixture("components/user-list.html");
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.definition("components/user-list.html", 2, 12)
+    else {
+        panic!("expected the controller class");
+    };
+    assert_eq!(
+        location.uri,
+        client.uri("components/ProfileBadgeController.ts")
+    );
+    assert_eq!(location.range.start, Position::new(0, 13));
+    assert_eq!(client.definition("components/user-list.html", 3, 12), None);
+}
+
+/// Where `components/directives.js` registers the directive on `line`
+fn directive(client: &TestClient, line: u32, name: &str) -> Option<GotoDefinitionResponse> {
+    Some(GotoDefinitionResponse::Scalar(
+        ls_core::protocol::Location::new(
+            client.uri("components/directives.js"),
+            Range::new(
+                Position::new(line, 14),
+                Position::new(line, 14 + name.len() as u32),
+            ),
+        ),
+    ))
+}
+
+#[test]
+fn definition_of_directive_attribute() {
+    let mut client = client();
+    client.open_fixture("components/toolbar.html");
+    let my_tooltip = directive(&client, 2, "myTooltip");
+    assert_eq!(
+        client.definition("components/toolbar.html", 0, 12),
+        my_tooltip
+    );
+    assert_eq!(
+        client.definition("components/toolbar.html", 2, 10),
+        my_tooltip
+    );
+    // declared with a function of the same file
+    assert_eq!(
+        client.definition("components/toolbar.html", 0, 32),
+        directive(&client, 5, "highlight")
+    );
+}
+
+#[test]
+fn definition_of_directive_respects_restrict() {
+    let mut client = client();
+    client.open_fixture("components/toolbar.html");
+    // `myTooltip` is restricted to attributes
+    assert_eq!(client.definition("components/toolbar.html", 1, 5), None);
+    assert_eq!(
+        client.definition("components/toolbar.html", 0, 54),
+        directive(&client, 5, "highlight")
+    );
+    assert_eq!(
+        client.definition("components/toolbar.html", 0, 65),
+        directive(&client, 6, "userCard")
+    );
+    assert_eq!(client.definition("components/toolbar.html", 0, 48), None);
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use ls_core::replay::{ReplayError, check_golden};
+
+/// Replays each recorded session in `tests/sessions` against its golden file.
+/// Run with `MYLS_UPDATE_GOLDEN=1` to accept the current output
+#[test]
+fn recorded_sessions() {
+    let sessions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sessions");
+    let mut recordings: Vec<_> = std::fs::read_dir(&sessions)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "jsonl")
+                && !path.to_string_lossy().ends_with(".golden.jsonl")
+        })
+        .collect();
+    recordings.sort();
+    assert!(!recordings.is_empty());
+    for recording in recordings {
+        let golden = recording.with_extension("golden.jsonl");
+        if let Err(err) = check_golden(&recording, &golden) {
+            panic!("{}: {err}", recording.display());
+        }
+    }
+}
+
+#[test]
+fn missing_golden_files_fail() {
+    if std::env::var_os("MYLS_UPDATE_GOLDEN").is_some() {
+        return;
+    }
+    let recording = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sessions/user-card.jsonl");
+    let golden =
+        std::env::temp_dir().join(format!("myls-missing-{}.golden.jsonl", std::process::id()));
+    let err = check_golden(&recording, &golden).unwrap_err();
+    assert!(matches!(err, ReplayError::MissingGolden(_)), "{err}");
+    assert!(!golden.exists());
+}
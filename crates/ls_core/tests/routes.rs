@@ -0,0 +1,112 @@
+use ls_core::{
+    protocol::{
+        InitializeParams, NumberOrString, Position, ReferenceContext, ReferenceParams,
+        TextDocumentIdentifier, TextDocumentPositionParams, WorkspaceFolder, request,
+    },
+    testing::TestClient,
+};
+
+mod common;
+
+use common::{definition, fixtures};
+
+/// `routes/routes.js` shows templates with controllers in `routes/controllers`,
+/// where their file names wouldn't find them
+fn client() -> TestClient {
+    let mut client = fixtures();
+    let folder = WorkspaceFolder {
+        uri: client.uri("routes"),
+        name: "routes".to_owned(),
+    };
+    client.initialize_with(InitializeParams {
+        workspace_folders: Some(vec![folder]),
+        ..Default::default()
+    });
+    client
+}
+
+#[test]
+fn state_binds_template_to_controller() {
+    let mut client = client();
+    client.open_fixture("routes/checkout/checkout.html");
+    assert_eq!(
+        definition(&mut client, "routes/checkout/checkout.html", 0, 16),
+        Some((
+            client.uri("routes/controllers/CheckoutController.ts"),
+            Position::new(1, 2)
+        ))
+    );
+    // `cart` is resolved by the state
+    assert_eq!(
+        definition(&mut client, "routes/checkout/checkout.html", 0, 45),
+        Some((client.uri("routes/routes.js"), Position::new(7, 8)))
+    );
+    assert!(
+        client
+            .diagnostics("routes/checkout/checkout.html")
+            .is_empty()
+    );
+}
+
+#[test]
+fn named_views_bind_their_template() {
+    let mut client = client();
+    client.open_fixture("routes/shop/header.html");
+    assert_eq!(
+        definition(&mut client, "routes/shop/header.html", 0, 15),
+        Some((
+            client.uri("routes/controllers/ShopHeaderController.ts"),
+            Position::new(1, 2)
+        ))
+    );
+}
+
+#[test]
+fn ng_route_binds_template_to_controller() {
+    let mut client = client();
+    client.open(
+        "routes/account.html",
+        "<p>{{ account.email }} {{ account.missing }}</p>\n",
+    );
+    let diagnostics = client.diagnostics("routes/account.html");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].code,
+        Some(NumberOrString::String("unknown-member".to_owned()))
+    );
+    assert_eq!(
+        diagnostics[0].message,
+        "'missing' doesn't exist on the controller 'AccountController.ts'"
+    );
+}
+
+#[test]
+fn references_in_templates_shown_by_routes() {
+    let mut client = client();
+    let references = client
+        .expect::<request::References>(ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: client.uri("routes/controllers/CheckoutController.ts"),
+                },
+                position: Position::new(1, 3),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        })
+        .unwrap_or_default();
+    let found: Vec<_> = references
+        .iter()
+        .map(|location| (location.uri.clone(), location.range.start))
+        .collect();
+    assert_eq!(
+        found,
+        [(
+            client.uri("routes/checkout/checkout.html"),
+            Position::new(0, 15)
+        )]
+    );
+}
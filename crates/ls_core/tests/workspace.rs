@@ -0,0 +1,129 @@
+use std::{fs, path::Path};
+
+use ls_core::{
+    Workspace,
+    protocol::{Position, PositionEncodingKind, Range, Uri},
+};
+
+mod common;
+
+use common::{FIXTURES, uri};
+
+fn workspace() -> Workspace {
+    Workspace::with_root(Path::new(FIXTURES).canonicalize().unwrap())
+}
+
+#[test]
+fn resolve_definition_in_open_template() {
+    let mut workspace = workspace();
+    workspace
+        .open(&uri("test.html"), "<p ng-if=\"vm.testMethod()\"></p>\n")
+        .unwrap();
+    let location = workspace
+        .resolve_definition(&uri("test.html"), Position::new(0, 14))
+        .unwrap();
+    assert_eq!(location.uri, uri("TestController.ts"));
+    assert_eq!(
+        location.range,
+        Range::new(Position::new(4, 2), Position::new(4, 12))
+    );
+}
+
+#[test]
+fn members_and_their_references() {
+    let mut workspace = workspace();
+    let members = workspace.members(&uri("TestController.ts"));
+    let names: Vec<_> = members.iter().map(|member| member.name.as_str()).collect();
+    assert_eq!(names, ["testValue", "testMethod"]);
+
+    workspace
+        .open(
+            &uri("test.html"),
+            "<p>{{ vm.testValue }}</p>\n<p>{{ vm.testValue + 1 }}</p>\n",
+        )
+        .unwrap();
+    let references = workspace.template_references(&members[0]);
+    let starts: Vec<_> = references
+        .iter()
+        .map(|location| location.range.start)
+        .collect();
+    assert_eq!(starts, [Position::new(0, 9), Position::new(1, 9)]);
+    assert!(workspace.template_references(&members[1]).is_empty());
+}
+
+#[test]
+fn diagnostics_of_templates_under_the_root() {
+    let workspace = workspace();
+    let templates: Vec<_> = workspace.diagnostics().collect();
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].uri, uri("test.html"));
+    assert_eq!(templates[0].diagnostics.len(), 1);
+}
+
+#[test]
+fn open_documents_replace_files() {
+    let mut workspace = workspace();
+    workspace
+        .open(&uri("test.html"), "<p>{{ vm.testValue }}</p>\n")
+        .unwrap();
+    assert!(workspace.template_diagnostics(&uri("test.html")).is_empty());
+    assert_eq!(workspace.diagnostics().count(), 0);
+    workspace.close(&uri("test.html"));
+    assert_eq!(workspace.diagnostics().count(), 1);
+}
+
+#[test]
+fn positions_in_other_encodings() {
+    let mut workspace = workspace().with_position_encoding(PositionEncodingKind::UTF8);
+    workspace
+        .open(
+            &uri("test.html"),
+            "<p title=\"日本\">{{ vm.testValue }}</p>\n",
+        )
+        .unwrap();
+    // byte 20 is in `{{ `, where UTF-16 code unit 20 would be on `testValue`
+    let location = workspace.resolve_definition(&uri("test.html"), Position::new(0, 20));
+    assert_eq!(location, None);
+    let location = workspace.resolve_definition(&uri("test.html"), Position::new(0, 25));
+    assert!(location.is_some());
+    let diagnostics = workspace.template_diagnostics(&uri("test.html"));
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn scripts_left_out_by_the_tsconfig() {
+    let root = std::env::temp_dir().join(format!("myls-tsconfig-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("dist")).unwrap();
+    fs::write(
+        root.join("tsconfig.json"),
+        "{\n  // the bundle registers everything again\n  \"compilerOptions\": { \"outDir\": \"dist\", },\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/filters.ts"),
+        "angular.module('app').filter('money', () => (value: number) => value);\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("dist/bundle.js"),
+        "angular.module('app').filter('percent', () => (value) => value);\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/price.html"),
+        "<p>{{ 1 | money }} {{ 2 | percent }}</p>\n",
+    )
+    .unwrap();
+    let workspace = Workspace::with_root(&root);
+    let template: Uri = format!("file://{}", root.join("src/price.html").display())
+        .parse()
+        .unwrap();
+    let diagnostics = workspace.template_diagnostics(&template);
+    fs::remove_dir_all(&root).unwrap();
+    let starts: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.range.start)
+        .collect();
+    assert_eq!(starts, [Position::new(0, 26)]);
+}
@@ -0,0 +1,173 @@
+use std::process::ExitCode;
+
+use ls_core::{
+    check::{FileDiagnostics, check_project},
+    protocol::{Diagnostic, DiagnosticSeverity, NumberOrString},
+};
+use serde_json::{Value, json};
+
+use crate::{
+    cli::{CheckArgs, CheckFormat, Cli},
+    load_config,
+};
+
+/// Prints the diagnostics of the project at `args.path`.
+/// Fails when any of them is an error
+pub fn check(cli: &Cli, args: &CheckArgs) -> ExitCode {
+    let Some(config) = load_config(cli, &args.path) else {
+        return ExitCode::from(2);
+    };
+    let files = match check_project(&args.path, config) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("lserver: couldn't check '{}': {err}", args.path.display());
+            return ExitCode::from(2);
+        }
+    };
+    match args.format {
+        CheckFormat::Human => print_human(&files),
+        CheckFormat::Json => println!("{:#}", to_json(&files)),
+        CheckFormat::Sarif => println!("{:#}", to_sarif(&files)),
+    }
+    let has_errors = files
+        .iter()
+        .flat_map(|file| &file.diagnostics)
+        .any(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+    if has_errors {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_human(files: &[FileDiagnostics]) {
+    let (mut errors, mut warnings) = (0, 0);
+    for file in files {
+        for diagnostic in &file.diagnostics {
+            let start = diagnostic.range.start;
+            match diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => errors += 1,
+                Some(DiagnosticSeverity::WARNING) => warnings += 1,
+                _ => {}
+            }
+            println!(
+                "{}:{}:{}: {}: {} [{}]",
+                path(file),
+                start.line + 1,
+                start.character + 1,
+                severity(diagnostic),
+                diagnostic.message,
+                code(diagnostic),
+            );
+        }
+    }
+    eprintln!(
+        "{errors} error(s), {warnings} warning(s) in {} file(s)",
+        files.len()
+    );
+}
+
+/// One object per diagnostic, with 1-based lines and columns
+fn to_json(files: &[FileDiagnostics]) -> Value {
+    files
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(|diagnostic| {
+                let range = diagnostic.range;
+                json!({
+                    "path": path(file),
+                    "line": range.start.line + 1,
+                    "column": range.start.character + 1,
+                    "endLine": range.end.line + 1,
+                    "endColumn": range.end.character + 1,
+                    "severity": severity(diagnostic),
+                    "code": code(diagnostic),
+                    "message": diagnostic.message,
+                })
+            })
+        })
+        .collect()
+}
+
+fn to_sarif(files: &[FileDiagnostics]) -> Value {
+    let mut rules: Vec<String> = files
+        .iter()
+        .flat_map(|file| file.diagnostics.iter().map(code))
+        .collect();
+    rules.sort();
+    rules.dedup();
+    let results: Vec<Value> = files
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(|diagnostic| {
+                let range = diagnostic.range;
+                let level = match diagnostic.severity {
+                    Some(DiagnosticSeverity::ERROR) => "error",
+                    Some(DiagnosticSeverity::WARNING) => "warning",
+                    _ => "note",
+                };
+                json!({
+                    "ruleId": code(diagnostic),
+                    "level": level,
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": path(file) },
+                            "region": {
+                                "startLine": range.start.line + 1,
+                                "startColumn": range.start.character + 1,
+                                "endLine": range.end.line + 1,
+                                "endColumn": range.end.character + 1,
+                            },
+                        },
+                    }],
+                })
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "myls",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                        .iter()
+                        .map(|rule| json!({ "id": rule }))
+                        .collect::<Vec<_>>(),
+                },
+            },
+            // `check` counts columns in code points
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+/// The path of `file` with `/` separators, as JSON and SARIF consumers expect
+fn path(file: &FileDiagnostics) -> String {
+    let path = file.path.to_string_lossy().replace('\\', "/");
+    match path.strip_prefix("./") {
+        Some(relative) => relative.to_owned(),
+        None => path,
+    }
+}
+
+fn severity(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        _ => "hint",
+    }
+}
+
+fn code(diagnostic: &Diagnostic) -> String {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => code.clone(),
+        Some(NumberOrString::Number(code)) => code.to_string(),
+        None => String::new(),
+    }
+}
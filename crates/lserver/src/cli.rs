@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::tracing::LogArgs;
+
+/// Language server for AngularJS templates.
+///
+/// Without a subcommand it serves an editor, the same as `lserver serve`
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Transport flags for when no subcommand is given,
+    /// as editors launch the server with e.g. `lserver --stdio`
+    #[command(flatten)]
+    pub serve: ServeArgs,
+    /// Project config file to use instead of looking one up
+    #[arg(long, global = true, env = "MYLS_CONFIG", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Record every message of the session to this JSONL file, for replaying it later
+    #[arg(long, global = true, env = "MYLS_RECORD", value_name = "PATH")]
+    pub record: Option<PathBuf>,
+    #[command(flatten)]
+    pub log: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Serve an editor over the language server protocol
+    Serve(ServeArgs),
+    /// Report template diagnostics for a project, like the editor would show them
+    Check(CheckArgs),
+    /// Print the registrations, routes and templateUrls of a project's scripts
+    Index(IndexArgs),
+    /// Print where the symbol at a position in a template is defined
+    Definition(DefinitionArgs),
+    /// Print where the controller member at a position is used in templates
+    References(ReferencesArgs),
+}
+
+/// How the server talks to the client. Stdio when none is given
+#[derive(Args, Debug, Default)]
+#[group(multiple = false)]
+pub struct ServeArgs {
+    /// Talk to the client over stdin and stdout
+    #[arg(long)]
+    pub stdio: bool,
+    /// Connect to the client listening on this TCP port of localhost
+    #[arg(long, value_name = "PORT")]
+    pub socket: Option<u16>,
+    /// Connect to the client listening on this unix socket or windows named pipe
+    #[arg(long, value_name = "PATH")]
+    pub pipe: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    /// Root directory of the project, or a single template
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    #[arg(long, value_enum, default_value_t = CheckFormat::Human)]
+    pub format: CheckFormat,
+}
+
+/// How `check` prints the diagnostics it finds
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckFormat {
+    /// One `path:line:col: severity: message` line per diagnostic
+    Human,
+    /// A JSON array of diagnostics
+    Json,
+    /// A SARIF 2.1.0 log, for code scanning tools
+    Sarif,
+}
+
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    /// Root directory of the project, or a single script
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct DefinitionArgs {
+    /// Position to look up, as `path/to/file.html:LINE:COL` with 1-based line and column
+    #[arg(value_name = "FILE:LINE:COL")]
+    pub location: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ReferencesArgs {
+    /// Position to look up, as `path/to/Controller.ts:LINE:COL` with 1-based line and column
+    #[arg(value_name = "FILE:LINE:COL")]
+    pub location: String,
+    /// Also print where the member is declared
+    #[arg(long)]
+    pub include_declaration: bool,
+}
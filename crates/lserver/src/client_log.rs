@@ -0,0 +1,67 @@
+use std::{cell::Cell, fmt::Write};
+
+use ls_core::{Client, protocol::MessageType};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+thread_local! {
+    /// Set while an event is being forwarded. Sending a message logs too,
+    /// and forwarding those logs would never end
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Forwards `tracing` events to the client as `window/logMessage`
+/// notifications, so they show up in the editor's output panel
+pub struct ClientLogLayer {
+    client: Client,
+}
+
+impl ClientLogLayer {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ClientLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if FORWARDING.replace(true) {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let message = format!("[{}] {}", metadata.target(), visitor.message);
+        self.client
+            .log_message(message_type(metadata.level()), message);
+        FORWARDING.set(false);
+    }
+}
+
+fn message_type(level: &Level) -> MessageType {
+    match *level {
+        Level::ERROR => MessageType::ERROR,
+        Level::WARN => MessageType::WARNING,
+        Level::INFO => MessageType::INFO,
+        Level::DEBUG | Level::TRACE => MessageType::LOG,
+    }
+}
+
+/// Writes the `message` of an event followed by its other fields as `name=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{value:?}{fields}");
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::process::ExitCode;
+
+use ls_core::index::{IndexEntry, index_project};
+
+use crate::{
+    cli::{Cli, IndexArgs},
+    load_config,
+};
+
+/// Prints what each script of the project at `args.path` declares,
+/// one `path:line:col: kind name` line per registration and `templateUrl`
+/// and one `path: route templateUrl` line per route
+pub fn index(cli: &Cli, args: &IndexArgs) -> ExitCode {
+    let Some(config) = load_config(cli, &args.path) else {
+        return ExitCode::from(2);
+    };
+    let scripts = match index_project(&args.path, config) {
+        Ok(scripts) => scripts,
+        Err(err) => {
+            eprintln!("lserver: couldn't index '{}': {err}", args.path.display());
+            return ExitCode::from(2);
+        }
+    };
+    for script in &scripts {
+        let path = script.path.to_string_lossy().replace('\\', "/");
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        for entry in &script.entries {
+            println!("{}", line(path, entry));
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn line(path: &str, entry: &IndexEntry) -> String {
+    let location = match entry.position {
+        Some(position) => format!("{path}:{}:{}", position.line + 1, position.character + 1),
+        None => path.to_owned(),
+    };
+    let mut line = format!("{location}: {} {}", entry.kind, entry.name);
+    if let Some(controller) = &entry.controller {
+        line.push_str(&format!(", controller {controller}"));
+    }
+    line
+}
@@ -1,12 +1,72 @@
+use std::{fs::File, io::BufWriter, path::Path, process::ExitCode};
+
 use ::tracing::debug;
-use ls_core::LServer;
+use clap::Parser;
+use cli::{Cli, Command, ServeArgs};
+use ls_core::Config;
 use tracing::setup_tracing;
 
+mod check;
+mod cli;
+mod client_log;
+mod index;
+mod query;
 mod tracing;
+mod transport;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match &cli.command {
+        None => serve(&cli, &cli.serve),
+        Some(Command::Serve(args)) => serve(&cli, args),
+        Some(Command::Check(args)) => with_tracing(&cli, || check::check(&cli, args)),
+        Some(Command::Index(args)) => with_tracing(&cli, || index::index(&cli, args)),
+        Some(Command::Definition(args)) => with_tracing(&cli, || query::definition(&cli, args)),
+        Some(Command::References(args)) => with_tracing(&cli, || query::references(&cli, args)),
+    }
+}
 
-fn main() {
-    let _worker_guard = setup_tracing();
+/// Runs `command` with tracing set up as the `--log-*` flags ask. Unlike
+/// when serving, there's no client to forward events to
+fn with_tracing(cli: &Cli, command: impl FnOnce() -> ExitCode) -> ExitCode {
+    let _worker_guard = setup_tracing(&cli.log, None);
+    command()
+}
+
+fn serve(cli: &Cli, args: &ServeArgs) -> ExitCode {
+    let mut ls = match transport::connect(args) {
+        Ok(ls) => ls,
+        Err(err) => {
+            eprintln!("lserver: couldn't connect to the client: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(path) = &cli.record {
+        match File::create(path) {
+            Ok(file) => ls.record_to(BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("lserver: couldn't create '{}': {err}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Some(path) = &cli.config {
+        ls.set_config_file(path);
+    }
+    let _worker_guard = setup_tracing(&cli.log, Some(ls.client()));
     debug!("================ init ==============");
-    let ls = LServer::new();
     ls.run();
+    ExitCode::SUCCESS
+}
+
+/// The config given with `--config`, or else the one of the project `start`
+/// is in. Prints why when it can't be loaded
+fn load_config(cli: &Cli, start: &Path) -> Option<Config> {
+    let config = match &cli.config {
+        Some(path) => Config::from_file(path),
+        None => Config::discover(start),
+    };
+    config
+        .inspect_err(|err| eprintln!("lserver: couldn't load the config: {err}"))
+        .ok()
 }
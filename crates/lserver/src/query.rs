@@ -0,0 +1,82 @@
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use ls_core::{
+    Config,
+    navigation::{FileLocation, definition_at, references_at},
+    protocol::Position,
+};
+
+use crate::{
+    cli::{Cli, DefinitionArgs, ReferencesArgs},
+    load_config,
+};
+
+pub fn definition(cli: &Cli, args: &DefinitionArgs) -> ExitCode {
+    run(cli, &args.location, |path, position, config| {
+        definition_at(path, position, config)
+    })
+}
+
+pub fn references(cli: &Cli, args: &ReferencesArgs) -> ExitCode {
+    run(cli, &args.location, |path, position, config| {
+        references_at(path, position, args.include_declaration, config)
+    })
+}
+
+/// Resolves `location` with `resolve` and prints a `file:line:col` line for
+/// each result. Fails when nothing was found
+fn run(
+    cli: &Cli,
+    location: &str,
+    resolve: impl FnOnce(&Path, Position, Config) -> io::Result<Vec<FileLocation>>,
+) -> ExitCode {
+    let Some((path, position)) = parse_location(location) else {
+        eprintln!("lserver: expected FILE:LINE:COL with 1-based LINE and COL, got '{location}'");
+        return ExitCode::from(2);
+    };
+    let Some(config) = load_config(cli, &path) else {
+        return ExitCode::from(2);
+    };
+    let locations = match resolve(&path, position, config) {
+        Ok(locations) => locations,
+        Err(err) => {
+            eprintln!("lserver: couldn't read '{}': {err}", path.display());
+            return ExitCode::from(2);
+        }
+    };
+    if locations.is_empty() {
+        eprintln!("lserver: nothing found at {location}");
+        return ExitCode::FAILURE;
+    }
+    let current_dir = env::current_dir().ok();
+    for location in locations {
+        let path = current_dir
+            .as_deref()
+            .and_then(|dir| location.path.strip_prefix(dir).ok())
+            .unwrap_or(&location.path);
+        println!(
+            "{}:{}:{}",
+            path.display(),
+            location.range.start.line + 1,
+            location.range.start.character + 1
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Splits `path:LINE:COL` into the path and a 0-based position.
+/// The path itself may contain `:`, as in `C:\app\page.html:3:7`
+fn parse_location(location: &str) -> Option<(PathBuf, Position)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column: u32 = parts.next()?.parse().ok()?;
+    let line: u32 = parts.next()?.parse().ok()?;
+    let path = parts.next().filter(|path| !path.is_empty())?;
+    Some((
+        PathBuf::from(path),
+        Position::new(line.checked_sub(1)?, column.checked_sub(1)?),
+    ))
+}
@@ -1,42 +1,146 @@
+use std::{io, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use ls_core::Client;
 use tracing_appender::{
     non_blocking::WorkerGuard,
     rolling::{RollingFileAppender, Rotation},
 };
-use tracing_subscriber::{filter::EnvFilter, fmt::format::FmtSpan, prelude::*};
-
-pub fn setup_tracing() -> WorkerGuard {
-    // 1. Create a rolling file appender
-    //    Logs will be saved to 'logs/my_app.log' and rotated daily.
-    //    'Rotation::DAILY' can be 'Rotation::HOURLY', 'Rotation::NEVER', or 'Rotation::MINUTELY'.
-    let file_appender = RollingFileAppender::new(
-        Rotation::DAILY,
-        "/Users/maheshbansod/projects/myls/logs",
-        "my_app.log",
-    );
-
-    // 2. You can also split output between stdout and a file if needed.
-    //    This creates a non-blocking writer for the file appender.
-    let (non_blocking_file_writer, guard) = tracing_appender::non_blocking(file_appender);
-
-    // 3. Configure the tracing subscriber
+use tracing_subscriber::{
+    Layer, Registry,
+    filter::EnvFilter,
+    fmt::{
+        self, MakeWriter,
+        format::{DefaultFields, FmtSpan, Format},
+    },
+    prelude::*,
+};
+
+use crate::client_log::ClientLogLayer;
+
+/// Name of the log file when `--log-file` isn't given
+const DEFAULT_LOG_FILE_NAME: &str = "myls.log";
+
+#[derive(Args, Debug)]
+pub struct LogArgs {
+    /// File to write logs to. It's rotated daily, with the date appended to its name.
+    /// Defaults to `myls.log` in the user's state directory, e.g. `~/.local/state/myls`
+    #[arg(long, global = true, env = "MYLS_LOG_FILE", value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// Which events to log, e.g. `debug` or `ls_core=trace`. Falls back to `RUST_LOG`
+    #[arg(long, global = true, env = "MYLS_LOG_LEVEL", value_name = "FILTER")]
+    pub log_level: Option<String>,
+    /// Format of each log line
+    #[arg(long, global = true, env = "MYLS_LOG_FORMAT", value_enum, default_value_t = LogFormat::Compact)]
+    pub log_format: LogFormat,
+    /// Also write logs to stderr
+    #[arg(long, global = true, env = "MYLS_LOG_STDERR")]
+    pub log_stderr: bool,
+    /// Which events to send to the editor as `window/logMessage`, `off` to send none
+    #[arg(
+        long,
+        global = true,
+        env = "MYLS_CLIENT_LOG_LEVEL",
+        value_name = "FILTER",
+        default_value = "info"
+    )]
+    pub client_log_level: String,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Compact,
+    Json,
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Sets up the global subscriber as asked by `args`.
+///
+/// The returned guard flushes the log file when dropped, so it must be held
+/// till the server exits. If the log file can't be created logs go to stderr instead.
+/// Events are also forwarded to `client` if there's one, filtered by `--client-log-level`
+pub fn setup_tracing(args: &LogArgs, client: Option<Client>) -> Option<WorkerGuard> {
+    let filter = match &args.log_level {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::from_default_env(),
+    };
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    let mut guard = None;
+    // The appender is non-blocking so that writing logs never holds up a response
+    match log_file_appender(args) {
+        Ok(file_appender) => {
+            let (non_blocking_file_writer, file_guard) =
+                tracing_appender::non_blocking(file_appender);
+            guard = Some(file_guard);
+            layers.push(format_layer(
+                fmt::layer()
+                    .with_writer(non_blocking_file_writer)
+                    .with_ansi(false), // Disable ANSI escape codes for file output
+                args.log_format,
+            ));
+        }
+        Err(err) => {
+            eprintln!("lserver: couldn't open the log file, logging to stderr instead: {err}");
+        }
+    }
+    if args.log_stderr || guard.is_none() {
+        // stdout is where the protocol messages go, so the console output must be stderr
+        layers.push(format_layer(
+            fmt::layer().with_writer(io::stderr),
+            args.log_format,
+        ));
+    }
+
     tracing_subscriber::registry()
-        // Add an EnvFilter to control log levels via an environment variable (e.g., RUST_LOG)
-        .with(EnvFilter::from_default_env())
-        // Add a formatter for the file output. You can use `Json` or `Full` for more structured/detailed logs.
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_writer(non_blocking_file_writer)
-                .with_ansi(false) // Disable ANSI escape codes for file output
-                .with_span_events(FmtSpan::CLOSE) // Log when spans close
-                .compact(), // Optional: Output logs in JSON format for easier parsing
-        )
-        // Optional: Add a console layer for simultaneous console output
-        // .with(
-        //     tracing_subscriber::fmt::layer()
-        //         .with_writer(io::stdout) // Output to stdout
-        //         .with_ansi(true) // Enable ANSI colors for console output
-        //         .with_span_events(FmtSpan::CLOSE),
-        // )
+        .with(layers.with_filter(filter))
+        .with(client.map(|client| {
+            ClientLogLayer::new(client).with_filter(EnvFilter::new(&args.client_log_level))
+        }))
         .init();
-    return guard;
+    guard
+}
+
+fn format_layer<W>(
+    layer: fmt::Layer<Registry, DefaultFields, Format, W>,
+    format: LogFormat,
+) -> BoxedLayer
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = layer.with_span_events(FmtSpan::CLOSE); // Log when spans close
+    match format {
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// A daily rotating appender for `--log-file`, or the default log file
+fn log_file_appender(args: &LogArgs) -> io::Result<RollingFileAppender> {
+    let log_file = args.log_file.clone().unwrap_or_else(default_log_file);
+    let directory = log_file
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let file_name = log_file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| DEFAULT_LOG_FILE_NAME.to_owned());
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(file_name)
+        .build(directory)
+        .map_err(io::Error::other)
+}
+
+/// `$XDG_STATE_HOME/myls/myls.log`, falling back to the cache directory
+/// on platforms without a state directory, and to the temp directory after that
+fn default_log_file() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("myls")
+        .join(DEFAULT_LOG_FILE_NAME)
 }
@@ -0,0 +1,37 @@
+use std::{
+    io,
+    net::{Ipv4Addr, TcpStream},
+    path::Path,
+};
+
+use ls_core::LServer;
+
+use crate::cli::ServeArgs;
+
+/// A server connected to the client through the transport picked in `args`
+pub fn connect(args: &ServeArgs) -> io::Result<LServer> {
+    if let Some(port) = args.socket {
+        let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        return Ok(LServer::with_io(stream.try_clone()?, stream));
+    }
+    if let Some(pipe) = &args.pipe {
+        return connect_pipe(pipe);
+    }
+    Ok(LServer::new())
+}
+
+#[cfg(unix)]
+fn connect_pipe(path: &Path) -> io::Result<LServer> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    Ok(LServer::with_io(stream.try_clone()?, stream))
+}
+
+#[cfg(windows)]
+fn connect_pipe(path: &Path) -> io::Result<LServer> {
+    // the client end of a named pipe is opened like a file
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    Ok(LServer::with_io(pipe.try_clone()?, pipe))
+}
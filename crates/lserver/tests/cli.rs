@@ -0,0 +1,216 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use serde_json::json;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../test");
+
+/// Runs `lserver` with `args` in the fixture directory `dir`, logging to a
+/// file in the temp directory
+fn lserver(dir: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lserver"))
+        .args(args)
+        .current_dir(Path::new(FIXTURES).join(dir))
+        .env(
+            "MYLS_LOG_FILE",
+            std::env::temp_dir().join("myls-cli-test.log"),
+        )
+        .env_remove("MYLS_CONFIG")
+        .output()
+        .expect("lserver runs")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn definition_through_a_route() {
+    // the controller is found through `routes.js`, not the template's file name
+    let output = lserver("routes", &["definition", "checkout/checkout.html:1:17"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "controllers/CheckoutController.ts:2:3\n");
+}
+
+#[test]
+fn references_from_a_controller() {
+    let output = lserver(
+        "routes",
+        &[
+            "references",
+            "--include-declaration",
+            "controllers/CheckoutController.ts:2:3",
+        ],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "controllers/CheckoutController.ts:2:3\ncheckout/checkout.html:1:16\n"
+    );
+}
+
+#[test]
+fn nothing_found_fails() {
+    let output = lserver("routes", &["definition", "checkout/checkout.html:1:1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn log_flags_apply_to_subcommands() {
+    let dir = std::env::temp_dir().join(format!("myls-cli-log-{}", std::process::id()));
+    let log_file = dir.join("check.log");
+    let output = lserver(
+        "inline",
+        &[
+            "check",
+            "--log-level",
+            "debug",
+            "--log-file",
+            log_file.to_str().unwrap(),
+        ],
+    );
+    // the log file is rotated daily, with the date appended to its name
+    let logs: Vec<_> = std::fs::read_dir(&dir)
+        .map(|entries| entries.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(output.status.success());
+    assert!(
+        logs.iter()
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("check.log")),
+        "no log file in {logs:?}"
+    );
+}
+
+#[test]
+fn json_logs() {
+    let dir = std::env::temp_dir().join(format!("myls-cli-json-{}", std::process::id()));
+    let output = lserver(
+        "inline",
+        &[
+            "check",
+            "--log-level",
+            "debug",
+            "--log-format",
+            "json",
+            "--log-file",
+            dir.join("check.log").to_str().unwrap(),
+        ],
+    );
+    let logs: String = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(output.status.success());
+    assert!(!logs.is_empty(), "nothing logged");
+    for line in logs.lines() {
+        let event: serde_json::Value =
+            serde_json::from_str(line).unwrap_or_else(|err| panic!("'{line}' isn't JSON: {err}"));
+        assert!(event.get("level").is_some(), "no level in '{line}'");
+    }
+}
+
+#[test]
+fn logs_to_stderr() {
+    let output = lserver("inline", &["check", "--log-level", "debug", "--log-stderr"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("DEBUG"));
+    // stdout is only for what the command prints
+    assert!(!stdout(&output).contains("DEBUG"));
+}
+
+#[test]
+fn logs_are_forwarded_to_the_client() {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_lserver"))
+        .args(["--client-log-level", "debug"])
+        .env(
+            "MYLS_LOG_FILE",
+            std::env::temp_dir().join("myls-cli-test.log"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("lserver runs");
+    let mut stdin = server.stdin.take().unwrap();
+    for message in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": {} } }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ] {
+        let message = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{message}", message.len()).unwrap();
+    }
+    drop(stdin);
+    let output = server.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert!(stdout.contains(r#""method":"window/logMessage""#));
+    // debug events are sent as plain logs
+    assert!(stdout.contains(r#""type":4"#));
+}
+
+#[test]
+fn definition_from_outside_the_project() {
+    let root = std::env::temp_dir().join(format!("myls-cli-root-{}", std::process::id()));
+    let app = root.join("project").join("app");
+    std::fs::create_dir_all(&app).unwrap();
+    std::fs::write(
+        app.join("c.js"),
+        "angular.module('a').component('userCard', {});\n",
+    )
+    .unwrap();
+    std::fs::write(app.join("x.html"), "<user-card></user-card>\n").unwrap();
+    let template = format!("{}:1:3", app.join("x.html").display());
+    // without a marker the project is the directory of the file
+    let alone = lserver("routes", &["definition", &template]);
+    std::fs::write(root.join("project").join("package.json"), "{}\n").unwrap();
+    std::fs::rename(app.join("c.js"), root.join("project").join("c.js")).unwrap();
+    // with one it's the directory the marker is in
+    let marked = lserver("routes", &["definition", &template]);
+    let _ = std::fs::remove_dir_all(&root);
+    assert!(alone.status.success());
+    assert_eq!(
+        stdout(&alone),
+        format!("{}:1:32\n", app.join("c.js").display())
+    );
+    assert!(marked.status.success());
+    assert_eq!(
+        stdout(&marked),
+        format!("{}:1:32\n", root.join("project").join("c.js").display())
+    );
+}
+
+#[test]
+fn index_lists_what_scripts_declare() {
+    let output = lserver("components", &["index"]);
+    assert!(output.status.success());
+    let listed = stdout(&output);
+    let lines: Vec<_> = listed.lines().collect();
+    assert_eq!(
+        lines[..3],
+        [
+            "SearchBox.ts:1:25: component searchBox",
+            "components.ts:1:34: component userCard, controller UserCardController",
+            "components.ts:2:17: templateUrl user-card.html",
+        ]
+    );
+
+    let output = lserver("routes", &["index", "routes.js"]);
+    assert!(output.status.success());
+    let listed = stdout(&output);
+    assert!(
+        listed.contains(
+            "routes.js: route routes/checkout/checkout.html, controller CheckoutController as checkout\n"
+        ),
+        "{listed}"
+    );
+}
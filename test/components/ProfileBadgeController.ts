@@ -0,0 +1,3 @@
+export class ProfileBadgeController {
+  name: string;
+}
@@ -0,0 +1,5 @@
+@Component({ selector: 'search-box', template: '<input>' })
+export class SearchBoxComponent {
+  @Input() query: string;
+  @Output('onSearch') search: Function;
+}
@@ -0,0 +1,9 @@
+angular.module('app').component('userCard', {
+  templateUrl: 'user-card.html',
+  controller: UserCardController,
+  bindings: {
+    user: '<',
+    onSave: '&',
+    'title': '@?',
+  },
+});
@@ -0,0 +1,15 @@
+angular
+  .module('app')
+  .directive('myTooltip', function () {
+    return { restrict: 'A', link: function () {} };
+  })
+  .directive('highlight', ['$timeout', highlight])
+  .directive('userCard', function () {
+    return { restrict: 'C' };
+  });
+
+function highlight($timeout) {
+  return {
+    restrict: 'AC',
+  };
+}
@@ -0,0 +1,15 @@
+angular
+  .module('app')
+  .filter('initials', function () {
+    return function (name, separator) {
+      return name
+        .split(' ')
+        .map((part) => part[0])
+        .join(separator || '');
+    };
+  })
+  .filter('truncate', ['limitToFilter', truncate]);
+
+function truncate(limitToFilter) {
+  return (text, length = 20) => limitToFilter(text, length) + '…';
+}
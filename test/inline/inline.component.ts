@@ -0,0 +1,20 @@
+class InlineController {
+  title = 'Orders';
+  refresh() {}
+}
+
+angular.module('app').component('inlineOrders', {
+  bindings: {
+    orders: '<',
+  },
+  controller: InlineController,
+  template: `
+    <h1 ng-click="$ctrl.refresh()">{{$ctrl.title}}</h1>
+    <p ng-repeat="order in $ctrl.orders">{{order.total | currency}}</p>
+  `,
+});
+
+angular.module('app').component('inlineBadge', {
+  bindings: { count: '<' },
+  template: '<span class="badge">{{$ctrl.count}}</span>',
+});
@@ -0,0 +1,16 @@
+class OrdersController {
+  orders = [];
+}
+
+angular.module('app').component('orders', {
+  templateUrl: 'app/orders/orders.html',
+  controller: OrdersController,
+});
+
+angular.module('app').component('ordersList', {
+  templateUrl: './orders-list.html',
+});
+
+angular.module('app').component('archive', {
+  templateUrl: 'app/orders/archive.html',
+});
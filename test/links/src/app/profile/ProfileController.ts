@@ -0,0 +1,3 @@
+export class ProfileController {
+  name = '';
+}
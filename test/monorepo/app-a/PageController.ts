@@ -0,0 +1,3 @@
+class PageController {
+  title: string;
+}
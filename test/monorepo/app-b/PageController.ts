@@ -0,0 +1,3 @@
+class PageController {
+  heading: string;
+}
@@ -0,0 +1,3 @@
+export class LineController {
+  quantity = 1;
+}
@@ -0,0 +1,6 @@
+export class OrderController {
+  total: number;
+  lines: string[] = [];
+
+  submit() {}
+}
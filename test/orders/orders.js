@@ -0,0 +1 @@
+angular.module('app').controller('OrderCtrl', ['$http', OrderController]);
@@ -0,0 +1,3 @@
+export class AccountController {
+  email: string;
+}
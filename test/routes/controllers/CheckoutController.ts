@@ -0,0 +1,3 @@
+export class CheckoutController {
+  total = 0;
+}
@@ -0,0 +1,3 @@
+export class ShopHeaderController {
+  title = 'Shop';
+}
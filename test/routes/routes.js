@@ -0,0 +1,29 @@
+angular.module('app').config(function ($stateProvider, $routeProvider) {
+  $stateProvider
+    .state('checkout', {
+      url: '/checkout',
+      templateUrl: 'routes/checkout/checkout.html',
+      controller: 'CheckoutController as checkout',
+      resolve: {
+        cart: function (CartService) {
+          return CartService.load();
+        },
+      },
+    })
+    .state('shop', {
+      url: '/shop',
+      views: {
+        header: {
+          templateUrl: './shop/header.html',
+          controller: ShopHeaderController,
+          controllerAs: 'header',
+        },
+      },
+    });
+
+  $routeProvider.when('/account', {
+    templateUrl: 'routes/account.html',
+    controller: 'AccountController',
+    controllerAs: 'account',
+  });
+});
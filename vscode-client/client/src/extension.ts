@@ -37,10 +37,21 @@ export function activate(context: ExtensionContext) {
   // Options to control the language client
   let clientOptions: LanguageClientOptions = {
     // Register the server for plain text documents
-    documentSelector: [{ scheme: 'file', language: 'html' }],
+    documentSelector: [
+      { scheme: 'file', language: 'html' },
+      // for the templateUrls of controllers and components
+      { scheme: 'file', language: 'typescript' },
+      { scheme: 'file', language: 'javascript' }
+    ],
     synchronize: {
-      // Notify the server about file changes to '.clientrc files contained in the workspace
-      fileEvents: workspace.createFileSystemWatcher('**/.clientrc')
+      // Notify the server about changes to the project config files in the workspace,
+      // and to the scripts it keeps the registrations of
+      fileEvents: [
+        workspace.createFileSystemWatcher('**/.mylsrc.json'),
+        workspace.createFileSystemWatcher('**/*.{ts,js}')
+      ],
+      // and to the 'myls' settings, which it then pulls again
+      configurationSection: 'myls'
     }
 
   };
@@ -58,6 +69,28 @@ export function activate(context: ExtensionContext) {
 
   // Start the client. This will also launch the server
   client.start();
+
+  context.subscriptions.push(
+    vscode.commands.registerCommand('myls.goToTemplate', () => goTo('myls/goToTemplate')),
+    vscode.commands.registerCommand('myls.goToController', () => goTo('myls/goToController'))
+  );
+}
+
+// Opens what the server answers `method` with for the active document,
+// a template or a controller
+async function goTo(method: string) {
+  const editor = vscode.window.activeTextEditor;
+  if (!editor) {
+    return;
+  }
+  const location = await client.sendRequest<{ uri: string } | null>(method, {
+    uri: editor.document.uri.toString()
+  });
+  if (!location) {
+    vscode.window.showInformationMessage('myls: nothing to go to from this file');
+    return;
+  }
+  await vscode.window.showTextDocument(vscode.Uri.parse(location.uri));
 }
 
 export function deactivate() {